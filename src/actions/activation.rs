@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+
+use zbus::blocking::{Connection, Proxy};
+use zbus::zvariant::Value;
+
+use super::util::session_bus;
+use crate::loader::util::{SherlockError, SherlockErrorType};
+
+pub const APPLICATION_INTERFACE: &str = "org.freedesktop.Application";
+
+/// Object path an app with the desktop file id `app_id` is exported at
+pub fn object_path(app_id: &str) -> String {
+    format!("/{}", app_id.replace('.', "/").replace('-', "_"))
+}
+
+/// Platform data passing the activation token in both of its common names
+pub fn platform_data(token: Option<&str>) -> HashMap<&'static str, Value<'_>> {
+    let mut data = HashMap::new();
+    if let Some(token) = token {
+        data.insert("activation-token", Value::from(token));
+        data.insert("desktop-startup-id", Value::from(token));
+    }
+    data
+}
+
+/// Activates the app, or one of its desktop actions, through its
+/// `org.freedesktop.Application` interface
+pub fn activate(
+    connection: &Connection,
+    app_id: &str,
+    action: Option<&str>,
+    token: Option<&str>,
+) -> zbus::Result<()> {
+    let path = object_path(app_id);
+    let proxy = Proxy::new(connection, app_id, path.as_str(), APPLICATION_INTERFACE)?;
+    let data = platform_data(token);
+    match action {
+        Some(action) => {
+            let parameter: Vec<Value> = Vec::new();
+            proxy.call::<_, _, ()>("ActivateAction", &(action, parameter, data))
+        }
+        None => proxy.call::<_, _, ()>("Activate", &(data,)),
+    }
+}
+
+/// Activates a `DBusActivatable` app on the session bus
+pub fn dbus_activate(
+    app_id: &str,
+    action: Option<&str>,
+    token: Option<&str>,
+) -> Result<(), SherlockError> {
+    activate(session_bus()?, app_id, action, token).map_err(|e| SherlockError {
+        error: SherlockErrorType::DBusMessageSendError(format!("Activate for {}", app_id)),
+        traceback: e.to_string(),
+    })
+}
+
+#[test]
+fn test_dbus_activation() {
+    use crate::daemon::dbus::p2p_connection;
+    use std::sync::{Arc, Mutex};
+    use zbus::zvariant::OwnedValue;
+
+    type Calls = Arc<Mutex<Vec<(String, Option<String>)>>>;
+    struct MockApplication {
+        calls: Calls,
+    }
+    fn token(data: &HashMap<String, OwnedValue>) -> Option<String> {
+        data.get("activation-token")
+            .and_then(|value| String::try_from(value.clone()).ok())
+    }
+    #[zbus::interface(name = "org.freedesktop.Application")]
+    impl MockApplication {
+        fn activate(&self, data: HashMap<String, OwnedValue>) {
+            let call = (String::from("activate"), token(&data));
+            self.calls.lock().unwrap().push(call);
+        }
+        fn activate_action(
+            &self,
+            action: String,
+            _parameter: Vec<OwnedValue>,
+            data: HashMap<String, OwnedValue>,
+        ) {
+            self.calls.lock().unwrap().push((action, token(&data)));
+        }
+    }
+
+    assert_eq!(object_path("org.gnome.Nautilus"), "/org/gnome/Nautilus");
+    assert_eq!(
+        object_path("org.gnome.font-viewer"),
+        "/org/gnome/font_viewer"
+    );
+
+    let calls: Calls = Arc::default();
+    let application = MockApplication {
+        calls: calls.clone(),
+    };
+    let (client, _server) = p2p_connection("/org/gnome/Nautilus", application);
+
+    activate(&client, "org.gnome.Nautilus", None, Some("token-1")).unwrap();
+    activate(&client, "org.gnome.Nautilus", Some("new-window"), None).unwrap();
+    assert_eq!(
+        calls.lock().unwrap().as_slice(),
+        &[
+            (String::from("activate"), Some(String::from("token-1"))),
+            (String::from("new-window"), None),
+        ]
+    );
+}
@@ -1,30 +1,59 @@
-use std::{
-    os::unix::process::CommandExt,
-    process::{Command, Stdio},
-};
+use std::path::{Path, PathBuf};
 
-pub fn applaunch(exec: &str) -> Option<()> {
-    let mut parts = exec
-        .trim()
-        .split_whitespace()
-        .filter(|s| !s.starts_with("%"));
+use gio::DesktopAppInfo;
+use gtk4::prelude::*;
 
-    let mut command = Command::new(parts.next()?);
-    command.args(parts);
+use super::activation::dbus_activate;
+use super::commandlaunch::{asynchronous_execution, parse_command};
+use super::exec::ExecContext;
+use crate::loader::util::SherlockError;
 
-    #[cfg(target_family = "unix")]
-    unsafe {
-        command
-            .stdin(Stdio::null())
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .pre_exec(|| {
-                nix::unistd::setsid().ok();
-                Ok(())
-            });
+/// How an app is started apart from its `Exec`
+#[derive(Debug, Default)]
+pub struct LaunchOptions {
+    pub working_dir: Option<PathBuf>,
+    /// Lets the compositor give focus to the new window
+    pub activation_token: Option<String>,
+    /// Start the app through `org.freedesktop.Application` instead
+    pub dbus_activatable: bool,
+    /// Desktop action that is activated over D-Bus
+    pub action: Option<String>,
+}
+
+/// Starts the `Exec` of an app. Its prefix, terminal and flags are already part
+/// of `exec`.
+pub fn applaunch(
+    exec: &str,
+    context: &ExecContext,
+    options: &LaunchOptions,
+) -> Result<(), SherlockError> {
+    // Scopes and D-Bus names use the desktop file id, e.g. org.gnome.Nautilus
+    let app_id = context
+        .desktop_file
+        .as_deref()
+        .and_then(|file| Path::new(file).file_stem())
+        .and_then(|stem| stem.to_str());
+    let token = options.activation_token.as_deref();
+
+    // The exec remains as fallback if the app can't be activated
+    if let (true, Some(app_id)) = (options.dbus_activatable, app_id) {
+        match dbus_activate(app_id, options.action.as_deref(), token) {
+            Ok(_) => return Ok(()),
+            Err(e) => eprintln!("Failed to activate {}: {}", app_id, e.traceback),
+        }
     }
-    let _ = command
-        .spawn()
-        .map_err(|e| eprintln!("Error executing command: {}", e));
-    None
+    for command in parse_command(exec.trim(), context)? {
+        asynchronous_execution(&command, options.working_dir.as_deref(), app_id, token)?;
+    }
+    Ok(())
+}
+
+/// Requests an activation token for the app from the compositor. On Wayland
+/// GDK obtains it through `xdg_activation_v1` for the focused surface.
+pub fn activation_token(widget: &impl IsA<gtk4::Widget>, desktop_file: &str) -> Option<String> {
+    let info = DesktopAppInfo::from_filename(desktop_file)?;
+    let context = widget.display().app_launch_context();
+    context
+        .startup_notify_id(&info, &[])
+        .map(|token| token.to_string())
 }
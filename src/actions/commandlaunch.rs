@@ -1,67 +1,132 @@
 use std::{
     os::unix::process::CommandExt,
+    path::Path,
     process::{Command, Stdio},
 };
 
+use super::exec::{parse_exec, ExecContext};
+use super::failures::{stderr_buffer, watch};
+use super::systemd::move_to_scope;
 use crate::{
     loader::util::{SherlockError, SherlockErrorType},
     CONFIG,
 };
 
 pub fn command_launch(exec: &str, keyword: &str) -> Result<(), SherlockError> {
+    let context = ExecContext {
+        keyword: Some(keyword.to_string()),
+        ..Default::default()
+    };
+    launch(exec, &context)
+}
+
+/// Starts every command chained in `exec` with the global prefix and flags
+pub fn launch(exec: &str, context: &ExecContext) -> Result<(), SherlockError> {
     let config = CONFIG.get().ok_or(SherlockError {
         error: SherlockErrorType::ConfigError(None),
         traceback: String::new(),
     })?;
-    let prefix = config
-        .behavior
-        .global_prefix
-        .as_ref()
-        .map_or(String::new(), |p| format!("{} ", p));
-    let flags = config
-        .behavior
-        .global_flags
-        .as_ref()
-        .map_or(String::new(), |f| format!(" {}", f));
-
-    let exec = exec.replace("{keyword}", &keyword);
-    let commands = exec.split("&").map(|s| s.trim()).filter(|s| !s.is_empty());
+    let prefix = global_args(config.behavior.global_prefix.as_deref())?;
+    let flags = global_args(config.behavior.global_flags.as_deref())?;
 
-    for command in commands {
-        asynchronous_execution(command, &prefix, &flags)?;
+    for command in parse_command(exec, context)? {
+        let args: Vec<String> = prefix
+            .iter()
+            .chain(command.iter())
+            .chain(flags.iter())
+            .cloned()
+            .collect();
+        asynchronous_execution(&args, None, None, None)?;
     }
     Ok(())
 }
 
-fn asynchronous_execution(cmd: &str, prefix: &str, flags: &str) -> Result<(), SherlockError> {
-    let raw_command = format!("{}{}{}", prefix, cmd, flags);
-    let mut parts = raw_command
-        .split_whitespace()
-        .filter(|s| !s.starts_with("%"));
+/// Parses `exec` into the commands it chains
+pub fn parse_command(exec: &str, context: &ExecContext) -> Result<Vec<Vec<String>>, SherlockError> {
+    parse_exec(exec, context).map_err(|e| SherlockError {
+        error: SherlockErrorType::CommandExecutionError(exec.to_string()),
+        traceback: e,
+    })
+}
+
+fn global_args(args: Option<&str>) -> Result<Vec<String>, SherlockError> {
+    match args {
+        Some(args) => Ok(parse_command(args, &ExecContext::default())?.concat()),
+        None => Ok(Vec::new()),
+    }
+}
 
-    let mut command = Command::new(parts.next().ok_or_else(|| SherlockError {
+/// Starts `args` detached from sherlock. As a daemon, sherlock reports the
+/// process if it fails right away. With `systemd_scopes` enabled, the process
+/// is moved into its own scope named after `name`, or the program if there is
+/// no name.
+pub fn asynchronous_execution(
+    args: &[String],
+    working_dir: Option<&Path>,
+    name: Option<&str>,
+    activation_token: Option<&str>,
+) -> Result<(), SherlockError> {
+    let (program, args) = args.split_first().ok_or_else(|| SherlockError {
         error: SherlockErrorType::CommandExecutionError(String::from(
             "The command list was empty.",
         )),
         traceback: String::from("Location: src/commandlaunch.rs"),
-    })?);
-    command.args(parts);
+    })?;
+    let name = name.unwrap_or_else(|| {
+        Path::new(program)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or(program)
+    });
+
+    let mut command = Command::new(program);
+    command.args(args);
+    if let Some(dir) = working_dir {
+        command.current_dir(dir);
+    }
+    // Never pass on the token sherlock itself was started with
+    match activation_token {
+        Some(token) => command
+            .env("XDG_ACTIVATION_TOKEN", token)
+            .env("DESKTOP_STARTUP_ID", token),
+        None => command
+            .env_remove("XDG_ACTIVATION_TOKEN")
+            .env_remove("DESKTOP_STARTUP_ID"),
+    };
+    // Only a daemon is still around to show failures after the window closed
+    let daemonized = CONFIG.get().is_some_and(|c| c.behavior.daemonize);
+    let (stderr, buffer) = match daemonized.then(stderr_buffer).flatten() {
+        Some((stderr, buffer)) => (stderr, Some(buffer)),
+        None => (Stdio::null(), None),
+    };
 
     unsafe {
         command
             .stdin(Stdio::null())
             .stdout(Stdio::null())
-            .stderr(Stdio::null())
+            .stderr(stderr)
             .pre_exec(|| {
                 nix::unistd::setsid().ok();
                 Ok(())
             });
     }
 
-    command.spawn().map_err(|e| SherlockError {
-        error: SherlockErrorType::CommandExecutionError(cmd.to_string()),
+    let child = command.spawn().map_err(|e| SherlockError {
+        error: SherlockErrorType::CommandExecutionError(program.to_string()),
         traceback: e.to_string(),
     })?;
 
+    // The process keeps running in sherlock's cgroup if this fails. The
+    // stderr buffer joins it, so that it outlives sherlock as well.
+    if CONFIG.get().is_some_and(|c| c.behavior.systemd_scopes) {
+        let mut pids = vec![child.id()];
+        pids.extend(buffer.as_ref().map(|b| b.id()));
+        if let Err(e) = move_to_scope(name, &pids) {
+            eprintln!("Failed to create a scope for {}: {}", name, e.traceback);
+        }
+    }
+    if daemonized {
+        watch(child, buffer, program.to_string());
+    }
     Ok(())
 }
@@ -0,0 +1,238 @@
+/// Values the field codes of an `Exec` line expand to
+#[derive(Debug, Clone, Default)]
+pub struct ExecContext {
+    /// Whether `%` starts a field code. Only the `Exec` lines of desktop
+    /// entries have them, other commands keep `%` as is, e.g. `date +%Y`.
+    pub field_codes: bool,
+    /// Replaces `%f` and `%F`
+    pub files: Vec<String>,
+    /// Replaces `%u` and `%U`. Files are used if there are no urls.
+    pub urls: Vec<String>,
+    /// `%i` expands to `--icon <icon>`
+    pub icon: Option<String>,
+    /// Replaces `%c`
+    pub name: Option<String>,
+    /// Replaces `%k`
+    pub desktop_file: Option<String>,
+    /// Replaces `{keyword}` within arguments
+    pub keyword: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Text(String),
+    Code(char),
+}
+
+#[derive(Debug, Default)]
+struct Token {
+    parts: Vec<Part>,
+    // Whether part of the token was quoted or escaped
+    literal: bool,
+}
+
+impl Token {
+    fn push(&mut self, c: char) {
+        match self.parts.last_mut() {
+            Some(Part::Text(text)) => text.push(c),
+            _ => self.parts.push(Part::Text(c.to_string())),
+        }
+    }
+    fn is_separator(&self) -> bool {
+        !self.literal && self.parts == [Part::Text(String::from("&"))]
+    }
+}
+
+/// Splits an `Exec` value into commands and their arguments.
+///
+/// Quoting follows the Desktop Entry Specification: arguments containing
+/// spaces are wrapped in double quotes, within which `"`, `` ` ``, `$` and `\`
+/// are escaped with a backslash. Outside of quotes a backslash escapes the next
+/// character. A standalone `&` separates commands that are started together,
+/// as in `systemctl suspend & swaylock`. A quoted or escaped `&`, or one within
+/// an argument like a url, is kept as is.
+pub fn parse_exec(exec: &str, context: &ExecContext) -> Result<Vec<Vec<String>>, String> {
+    let mut commands: Vec<Vec<String>> = Vec::new();
+    let mut current: Vec<String> = Vec::new();
+    for token in tokenize(exec, context.field_codes)? {
+        if token.is_separator() {
+            commands.push(std::mem::take(&mut current));
+            continue;
+        }
+        current.extend(expand(token.parts, context));
+    }
+    commands.push(current);
+    commands.retain(|command| !command.is_empty());
+    Ok(commands)
+}
+
+fn tokenize(exec: &str, field_codes: bool) -> Result<Vec<Token>, String> {
+    let mut tokens: Vec<Token> = Vec::new();
+    // A token is started by any character, including empty quotes
+    let mut token: Option<Token> = None;
+    let mut in_quotes = false;
+
+    let mut chars = exec.chars();
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            let current = token.get_or_insert_with(Token::default);
+            match c {
+                '"' => in_quotes = false,
+                '\\' => match chars.next() {
+                    Some(escaped) => current.push(escaped),
+                    None => return Err(String::from("Trailing backslash in quoted argument")),
+                },
+                _ => current.push(c),
+            }
+            continue;
+        }
+        if c.is_whitespace() {
+            tokens.extend(token.take());
+            continue;
+        }
+        let current = token.get_or_insert_with(Token::default);
+        match c {
+            '"' => {
+                in_quotes = true;
+                current.literal = true;
+            }
+            '\\' => {
+                current.push(chars.next().unwrap_or('\\'));
+                current.literal = true;
+            }
+            '%' if field_codes => current.parts.push(Part::Code(chars.next().unwrap_or('%'))),
+            _ => current.push(c),
+        }
+    }
+    if in_quotes {
+        return Err(String::from("Unterminated quote"));
+    }
+    tokens.extend(token);
+    Ok(tokens)
+}
+
+fn expand(token: Vec<Part>, context: &ExecContext) -> Vec<String> {
+    let urls = if context.urls.is_empty() {
+        &context.files
+    } else {
+        &context.urls
+    };
+
+    // Codes standing alone may expand to several or no arguments
+    if let [Part::Code(code)] = token.as_slice() {
+        return match code {
+            'F' => context.files.clone(),
+            'U' => urls.clone(),
+            'f' => context.files.first().cloned().into_iter().collect(),
+            'u' => urls.first().cloned().into_iter().collect(),
+            'i' => context
+                .icon
+                .as_ref()
+                .map_or_else(Vec::new, |icon| vec![String::from("--icon"), icon.clone()]),
+            'c' => context.name.clone().into_iter().collect(),
+            'k' => context.desktop_file.clone().into_iter().collect(),
+            '%' => vec![String::from("%")],
+            // Deprecated and unknown codes are dropped
+            _ => Vec::new(),
+        };
+    }
+
+    let mut arg = String::new();
+    for part in token {
+        match part {
+            Part::Text(text) => match &context.keyword {
+                Some(keyword) => arg.push_str(&text.replace("{keyword}", keyword)),
+                None => arg.push_str(&text),
+            },
+            Part::Code(code) => match code {
+                'f' | 'F' => arg.push_str(&context.files.join(" ")),
+                'u' | 'U' => arg.push_str(&urls.join(" ")),
+                'i' => arg.push_str(context.icon.as_deref().unwrap_or_default()),
+                'c' => arg.push_str(context.name.as_deref().unwrap_or_default()),
+                'k' => arg.push_str(context.desktop_file.as_deref().unwrap_or_default()),
+                '%' => arg.push('%'),
+                _ => {}
+            },
+        }
+    }
+    vec![arg]
+}
+
+#[test]
+fn test_parse_exec() {
+    let desktop = ExecContext {
+        field_codes: true,
+        ..Default::default()
+    };
+    let parse = |exec: &str| parse_exec(exec, &desktop).unwrap();
+
+    assert_eq!(parse("firefox %u"), vec![vec!["firefox"]]);
+    assert_eq!(
+        parse(r#"sh -c "echo \"foo bar\" \$HOME""#),
+        vec![vec!["sh", "-c", "echo \"foo bar\" $HOME"]]
+    );
+    assert_eq!(
+        parse(r#"/opt/My\ App/run "" --x=100%%"#),
+        vec![vec!["/opt/My App/run", "", "--x=100%"]]
+    );
+    assert!(parse_exec("sh -c \"unterminated", &desktop).is_err());
+
+    // Chaining only happens on a standalone ampersand
+    assert_eq!(
+        parse("systemctl suspend & swaylock"),
+        vec![vec!["systemctl", "suspend"], vec!["swaylock"]]
+    );
+    assert_eq!(
+        parse("xdg-open https://example.com/?a=1&b=2"),
+        vec![vec!["xdg-open", "https://example.com/?a=1&b=2"]]
+    );
+    assert_eq!(parse(r#"echo "&" \&"#), vec![vec!["echo", "&", "&"]]);
+
+    let context = ExecContext {
+        field_codes: true,
+        files: vec![String::from("/tmp/a b.txt"), String::from("/tmp/c.txt")],
+        urls: vec![],
+        icon: Some(String::from("gimp")),
+        name: Some(String::from("GNU Image Manipulation Program")),
+        desktop_file: Some(String::from("/usr/share/applications/gimp.desktop")),
+        keyword: Some(String::from("two words")),
+    };
+    assert_eq!(
+        parse_exec("gimp %i --name=%c %F", &context).unwrap(),
+        vec![vec![
+            "gimp",
+            "--icon",
+            "gimp",
+            "--name=GNU Image Manipulation Program",
+            "/tmp/a b.txt",
+            "/tmp/c.txt"
+        ]]
+    );
+    assert_eq!(
+        parse_exec("open %u --from %k %d", &context).unwrap(),
+        vec![vec![
+            "open",
+            "/tmp/a b.txt",
+            "--from",
+            "/usr/share/applications/gimp.desktop"
+        ]]
+    );
+    assert_eq!(
+        parse_exec("search --query={keyword}", &context).unwrap(),
+        vec![vec!["search", "--query=two words"]]
+    );
+
+    // Commands that aren't desktop entries pass percent signs through
+    let command = ExecContext {
+        keyword: Some(String::from("notes")),
+        ..Default::default()
+    };
+    assert_eq!(
+        parse_exec("date +%Y-%m-%d", &command).unwrap(),
+        vec![vec!["date", "+%Y-%m-%d"]]
+    );
+    assert_eq!(
+        parse_exec("printf %s {keyword}", &command).unwrap(),
+        vec![vec!["printf", "%s", "notes"]]
+    );
+}
@@ -0,0 +1,242 @@
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{Read, Write};
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::loader::frecency::unix_now;
+use crate::loader::util::{SherlockError, SherlockErrorType};
+
+// Exits within this time after the launch count as failed launches
+const GRACE_PERIOD: Duration = Duration::from_secs(3);
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+// Bytes of stderr kept of each launch
+const STDERR_BYTES: usize = 16 * 1024;
+// Time the stderr of a failed launch may take to arrive
+const STDERR_TIMEOUT: Duration = Duration::from_millis(500);
+// Lines of stderr shown with a failure
+const STDERR_LINES: usize = 5;
+// The launch log is started over once it grows beyond this size
+const MAX_LOG_SIZE: u64 = 512 * 1024;
+
+// Failures not yet shown to the user
+static FAILURES: Mutex<Vec<SherlockError>> = Mutex::new(Vec::new());
+// Processes that outlived their grace period, reaped on later launches
+static DETACHED: Mutex<Vec<Child>> = Mutex::new(Vec::new());
+
+/// `$XDG_STATE_HOME/sherlock`, by default `~/.local/state/sherlock`
+pub fn state_dir() -> Option<PathBuf> {
+    let state_home = match env::var("XDG_STATE_HOME") {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var("HOME").ok()?).join(".local/state"),
+    };
+    Some(state_home.join("sherlock"))
+}
+
+/// Starts `tail` to keep the last bytes written to the returned stdio. The
+/// launched program writes into a pipe that `tail` drains for as long as it
+/// runs, so its stderr neither grows without bounds nor breaks once sherlock
+/// stops reading.
+pub fn stderr_buffer() -> Option<(Stdio, Child)> {
+    let mut command = Command::new("tail");
+    command
+        .args(["-c", &STDERR_BYTES.to_string()])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null());
+    unsafe {
+        command.pre_exec(|| {
+            nix::unistd::setsid().ok();
+            Ok(())
+        });
+    }
+    let mut buffer = command.spawn().ok()?;
+    let input = buffer.stdin.take()?;
+    Some((Stdio::from(input), buffer))
+}
+
+/// Logs a failed launch and keeps it to be shown on the error page
+pub fn report(error: SherlockError) {
+    let (name, message) = error.error.get_message();
+    eprintln!("{}: {}", name, message);
+    if let Some(path) = state_dir().map(|dir| dir.join("launch.log")) {
+        let _ = append_log(&path, &log_entry(unix_now(), &error));
+    }
+    if let Ok(mut failures) = FAILURES.lock() {
+        failures.push(error);
+    }
+}
+
+pub fn has_failures() -> bool {
+    FAILURES.lock().is_ok_and(|f| !f.is_empty())
+}
+
+/// Failures that were not shown yet
+pub fn take_failures() -> Vec<SherlockError> {
+    FAILURES
+        .lock()
+        .map(|mut f| std::mem::take(&mut *f))
+        .unwrap_or_default()
+}
+
+/// Reports `child` if it fails within the grace period. Afterwards the
+/// watching thread ends and the processes are reaped on a later launch.
+pub fn watch(child: Child, buffer: Option<Child>, command: String) {
+    reap();
+    thread::spawn(move || {
+        let mut child = child;
+        let mut buffer = buffer;
+        if let Some(status) = early_exit(&mut child, GRACE_PERIOD) {
+            let stderr = buffer.as_mut().map(read_buffer).unwrap_or_default();
+            report(exit_error(&command, status, &stderr));
+        } else {
+            detach(child);
+        }
+        if let Some(mut buffer) = buffer {
+            // Without a reader, `tail` ends with the stream it buffers
+            buffer.stdout.take();
+            detach(buffer);
+        }
+    });
+}
+
+fn detach(child: Child) {
+    if let Ok(mut detached) = DETACHED.lock() {
+        detached.push(child);
+    }
+}
+
+/// Reaps the detached processes that exited in the meantime
+fn reap() {
+    if let Ok(mut detached) = DETACHED.lock() {
+        detached.retain_mut(|child| matches!(child.try_wait(), Ok(None)));
+    }
+}
+
+/// The status of `child` if it exits unsuccessfully within `grace`
+pub fn early_exit(child: &mut Child, grace: Duration) -> Option<ExitStatus> {
+    let start = Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if start.elapsed() < grace => thread::sleep(POLL_INTERVAL),
+            _ => return None,
+        }
+    };
+    (!status.success()).then_some(status)
+}
+
+/// The output kept by a buffer of `stderr_buffer`. It is only available once
+/// every process writing to it exited, which processes that forked into the
+/// background may delay beyond the timeout.
+fn read_buffer(buffer: &mut Child) -> String {
+    let start = Instant::now();
+    while matches!(buffer.try_wait(), Ok(None)) {
+        if start.elapsed() > STDERR_TIMEOUT {
+            return String::new();
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+    let mut output = Vec::new();
+    if let Some(mut stdout) = buffer.stdout.take() {
+        let _ = stdout.read_to_end(&mut output);
+    }
+    String::from_utf8_lossy(&output).into_owned()
+}
+
+fn exit_error(command: &str, status: ExitStatus, stderr: &str) -> SherlockError {
+    let mut traceback = match (status.code(), status.signal()) {
+        (Some(code), _) => format!("Exited with status {}", code),
+        (_, Some(signal)) => format!("Killed by signal {}", signal),
+        _ => String::from("Exited"),
+    };
+    for line in tail(stderr, STDERR_LINES) {
+        traceback.push('\n');
+        traceback.push_str(line);
+    }
+    SherlockError {
+        error: SherlockErrorType::CommandExitError(command.to_string()),
+        traceback,
+    }
+}
+
+/// The last `n` non-empty lines of `text`
+fn tail(text: &str, n: usize) -> Vec<&str> {
+    let lines: Vec<&str> = text.lines().filter(|l| !l.trim().is_empty()).collect();
+    lines[lines.len().saturating_sub(n)..].to_vec()
+}
+
+fn log_entry(time: u64, error: &SherlockError) -> String {
+    let (name, message) = error.error.get_message();
+    let mut entry = format!("[{}] {}: {}\n", time, name, message);
+    for line in error.traceback.lines() {
+        entry.push_str("    ");
+        entry.push_str(line);
+        entry.push('\n');
+    }
+    entry
+}
+
+fn append_log(path: &Path, entry: &str) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let full = fs::metadata(path).is_ok_and(|m| m.len() > MAX_LOG_SIZE);
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(!full)
+        .write(true)
+        .truncate(full)
+        .open(path)?;
+    file.write_all(entry.as_bytes())
+}
+
+#[test]
+fn test_launch_failures() {
+    assert_eq!(tail("a\nb\n\nc\n", 2), vec!["b", "c"]);
+    assert_eq!(tail("only", 5), vec!["only"]);
+
+    let spawn = |script: &str| {
+        let (stderr, buffer) = stderr_buffer().unwrap();
+        let child = Command::new("sh")
+            .args(["-c", script])
+            .stdout(Stdio::null())
+            .stderr(stderr)
+            .spawn()
+            .unwrap();
+        (child, buffer)
+    };
+    let grace = Duration::from_secs(2);
+
+    // A failing command is reported with its last stderr lines
+    let (mut child, mut buffer) = spawn("echo starting >&2; echo 'no such file' >&2; exit 3");
+    let status = early_exit(&mut child, grace).unwrap();
+    let error = exit_error("broken", status, &read_buffer(&mut buffer));
+    assert!(matches!(error.error, SherlockErrorType::CommandExitError(ref c) if c == "broken"));
+    assert_eq!(
+        error.traceback,
+        "Exited with status 3\nstarting\nno such file"
+    );
+    let entry = log_entry(100, &error);
+    assert!(entry.starts_with("[100] CommandExitError: "));
+    assert!(entry.ends_with("    no such file\n"));
+
+    // Only the end of long output is kept
+    let (mut child, mut buffer) = spawn("yes error | head -n 100000 >&2; exit 1");
+    assert!(early_exit(&mut child, grace).is_some());
+    let output = read_buffer(&mut buffer);
+    assert_eq!(output.len(), STDERR_BYTES);
+    assert!(output.ends_with("error\n"));
+
+    // Successful and long running commands are not reported
+    let (mut child, _) = spawn("exit 0");
+    assert!(early_exit(&mut child, grace).is_none());
+    let (mut child, _) = spawn("sleep 5");
+    assert!(early_exit(&mut child, Duration::from_millis(200)).is_none());
+    let _ = child.kill();
+    let _ = child.wait();
+}
@@ -1,18 +1,26 @@
 use std::collections::HashMap;
+use std::path::PathBuf;
 
+use applaunch::LaunchOptions;
+use exec::ExecContext;
 use gio::glib::variant::ToVariant;
 use gtk4::prelude::WidgetExt;
 use teamslaunch::teamslaunch;
 
 use crate::{
+    daemon::dbus,
     g_subclasses::sherlock_row::SherlockRow,
     launcher::{audio_launcher::MusicPlayerLauncher, process_launcher::ProcessLauncher},
-    loader::launcher_loader::CounterReader,
+    loader::{frecency::record_launch, query_memory::remember_selection, util::SherlockError},
     ui::user::display_raw,
 };
 
+pub mod activation;
 pub mod applaunch;
 pub mod commandlaunch;
+pub mod exec;
+pub mod failures;
+pub mod systemd;
 pub mod teamslaunch;
 pub mod util;
 pub mod websearch;
@@ -25,32 +33,60 @@ pub fn execute_from_attrs(row: &SherlockRow, attrs: &HashMap<String, String>) {
         .collect();
 
     if let Some(method) = attrs.get("method") {
+        let value = ["exec", "result", "keyword"]
+            .iter()
+            .find_map(|key| attrs.get(*key))
+            .map_or("", |s| s.as_str());
+        dbus::emit_result_activated(method, value);
+
         match method.as_str() {
             "categories" => {
                 attrs.get("exec").map(|mode| {
                     let _ = row.activate_action("win.switch-mode", Some(&mode.to_variant()));
                 });
             }
+            "set_search" => {
+                let keyword = attrs.get("keyword").map_or("", |s| s.as_str());
+                let _ = row.activate_action("win.set-search", Some(&keyword.to_variant()));
+            }
             "app_launcher" => {
                 let exec = attrs.get("exec").map_or("", |s| s.as_str());
-                applaunch::applaunch(exec);
-                increment(&exec);
-                let _ = row.activate_action("win.close", None);
+                let query = attrs.get("query").map_or("", |s| s.as_str());
+                let context = ExecContext {
+                    field_codes: true,
+                    icon: attrs.get("icon").cloned(),
+                    name: attrs.get("name").cloned(),
+                    desktop_file: attrs.get("desktop_file").cloned(),
+                    ..Default::default()
+                };
+                let activation_token = attrs
+                    .get("desktop_file")
+                    .filter(|_| attrs.contains_key("startup_notify"))
+                    .and_then(|file| applaunch::activation_token(row, file));
+                let options = LaunchOptions {
+                    working_dir: attrs.get("working_dir").map(PathBuf::from),
+                    activation_token,
+                    dbus_activatable: attrs.contains_key("dbus_activatable"),
+                    action: attrs.get("action").cloned(),
+                };
+                let result = applaunch::applaunch(exec, &context, &options);
+                finish_launch(row, result, exec, query);
             }
             "web_launcher" => {
                 let query = attrs.get("keyword").map_or("", |s| s.as_str());
                 let engine = attrs.get("engine").map_or("", |s| s.as_str());
-                let _ = websearch::websearch(engine, query);
+                let result = websearch::websearch(engine, query);
                 let exec = format!("websearch-{}", engine);
-                increment(&exec);
-                let _ = row.activate_action("win.close", None);
+                // The query is free text, e.g. a search or clipboard content,
+                // so only the launch itself is recorded
+                finish_launch(row, result, &exec, "");
             }
             "command" => {
                 let exec = attrs.get("exec").map_or("", |s| s.as_str());
                 let keyword = attrs.get("keyword").map_or("", |s| s.as_str());
-                let _ = commandlaunch::command_launch(exec, keyword);
-                increment(&exec);
-                let _ = row.activate_action("win.close", None);
+                let query = attrs.get("query").map_or("", |s| s.as_str());
+                let result = commandlaunch::command_launch(exec, keyword);
+                finish_launch(row, result, exec, query);
             }
             "copy" => {
                 if let Some(field) = attrs.get("field") {
@@ -141,8 +177,20 @@ pub fn get_attrs_map(in_attrs: Vec<(&str, &str)>) -> HashMap<String, String> {
         .map(|(k, v)| (k.to_string(), v.to_string()))
         .collect()
 }
-fn increment(key: &str) {
-    if let Ok(count_reader) = CounterReader::new() {
-        let _ = count_reader.increment(key);
-    };
+/// Closes the window after a launch, or shows the error page if it failed
+fn finish_launch(row: &SherlockRow, result: Result<(), SherlockError>, exec: &str, query: &str) {
+    match result {
+        Ok(_) => {
+            increment(exec, query);
+            let _ = row.activate_action("win.close", None);
+        }
+        Err(e) => {
+            failures::report(e);
+            let _ = row.activate_action("win.show-launch-errors", None);
+        }
+    }
+}
+fn increment(key: &str, query: &str) {
+    let _ = record_launch(key);
+    let _ = remember_selection(query, key);
 }
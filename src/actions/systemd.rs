@@ -0,0 +1,119 @@
+use zbus::blocking::{Connection, Proxy};
+use zbus::zvariant::{OwnedObjectPath, Value};
+
+use super::util::session_bus;
+use crate::loader::util::{SherlockError, SherlockErrorType};
+
+pub const SYSTEMD_DESTINATION: &str = "org.freedesktop.systemd1";
+pub const SYSTEMD_PATH: &str = "/org/freedesktop/systemd1";
+pub const SYSTEMD_MANAGER: &str = "org.freedesktop.systemd1.Manager";
+
+/// Name of the scope an app is placed in, `app-sherlock-<name>-<id>.scope`.
+/// The name is escaped like `systemd-escape` does.
+pub fn scope_name(name: &str, id: u32) -> String {
+    let mut escaped = String::new();
+    for (i, byte) in name.bytes().enumerate() {
+        let allowed = byte.is_ascii_alphanumeric() || byte == b'_' || (byte == b'.' && i > 0);
+        if allowed {
+            escaped.push(byte as char);
+        } else {
+            escaped.push_str(&format!("\\x{:02x}", byte));
+        }
+    }
+    format!("app-sherlock-{}-{}.scope", escaped, id)
+}
+
+/// Asks systemd to move `pids` into the new transient scope `unit`
+pub fn start_scope(
+    connection: &Connection,
+    unit: &str,
+    description: &str,
+    pids: &[u32],
+) -> zbus::Result<OwnedObjectPath> {
+    let proxy = Proxy::new(
+        connection,
+        SYSTEMD_DESTINATION,
+        SYSTEMD_PATH,
+        SYSTEMD_MANAGER,
+    )?;
+    let properties: Vec<(&str, Value)> = vec![
+        ("Description", Value::from(description)),
+        ("PIDs", Value::from(pids.to_vec())),
+        ("CollectMode", Value::from("inactive-or-failed")),
+    ];
+    let aux: Vec<(&str, Vec<(&str, Value)>)> = Vec::new();
+    proxy.call("StartTransientUnit", &(unit, "fail", properties, aux))
+}
+
+/// Places the running processes `pids` into their own scope of the user
+/// manager. The scope is named after the first one.
+pub fn move_to_scope(name: &str, pids: &[u32]) -> Result<(), SherlockError> {
+    let connection = session_bus()?;
+    let unit = scope_name(name, pids.first().copied().unwrap_or_default());
+    let description = format!("{} launched by Sherlock", name);
+    start_scope(connection, &unit, &description, pids).map_err(|e| SherlockError {
+        error: SherlockErrorType::DBusMessageSendError(format!("StartTransientUnit for {}", unit)),
+        traceback: e.to_string(),
+    })?;
+    Ok(())
+}
+
+#[test]
+fn test_transient_scope() {
+    use crate::daemon::dbus::p2p_connection;
+    use std::sync::{Arc, Mutex};
+    use zbus::zvariant::OwnedValue;
+
+    type Units = Arc<Mutex<Vec<(String, String, Vec<u32>)>>>;
+    struct MockManager {
+        units: Units,
+    }
+    #[zbus::interface(name = "org.freedesktop.systemd1.Manager")]
+    impl MockManager {
+        fn start_transient_unit(
+            &self,
+            name: String,
+            mode: String,
+            properties: Vec<(String, OwnedValue)>,
+            _aux: Vec<(String, Vec<(String, OwnedValue)>)>,
+        ) -> zbus::fdo::Result<OwnedObjectPath> {
+            let pids = properties
+                .into_iter()
+                .find(|(key, _)| key == "PIDs")
+                .and_then(|(_, value)| Vec::<u32>::try_from(value).ok())
+                .unwrap_or_default();
+            self.units.lock().unwrap().push((name, mode, pids));
+            OwnedObjectPath::try_from("/org/freedesktop/systemd1/job/1")
+                .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))
+        }
+    }
+
+    assert_eq!(scope_name("firefox", 42), "app-sherlock-firefox-42.scope");
+    assert_eq!(
+        scope_name("org.gnome-Clocks", 7),
+        "app-sherlock-org.gnome\\x2dClocks-7.scope"
+    );
+
+    let units: Units = Arc::default();
+    let manager = MockManager {
+        units: units.clone(),
+    };
+    let (client, _server) = p2p_connection(SYSTEMD_PATH, manager);
+
+    let job = start_scope(
+        &client,
+        "app-sherlock-firefox-42.scope",
+        "Firefox",
+        &[42, 43],
+    )
+    .unwrap();
+    assert_eq!(job.as_str(), "/org/freedesktop/systemd1/job/1");
+    assert_eq!(
+        units.lock().unwrap().as_slice(),
+        &[(
+            String::from("app-sherlock-firefox-42.scope"),
+            String::from("fail"),
+            vec![42, 43]
+        )]
+    );
+}
@@ -1,7 +1,23 @@
+use std::sync::OnceLock;
+
 use cli_clipboard::{ClipboardContext, ClipboardProvider};
+use zbus::blocking::Connection;
 
 use crate::loader::util::{SherlockError, SherlockErrorType};
 
+static SESSION_BUS: OnceLock<Option<Connection>> = OnceLock::new();
+
+/// Blocking connection to the session bus, shared by all launches
+pub fn session_bus() -> Result<&'static Connection, SherlockError> {
+    SESSION_BUS
+        .get_or_init(|| Connection::session().ok())
+        .as_ref()
+        .ok_or_else(|| SherlockError {
+            error: SherlockErrorType::DBusConnectionError,
+            traceback: String::from("Location: src/actions/util.rs"),
+        })
+}
+
 pub fn copy_to_clipboard(string: &str) -> Result<(), SherlockError> {
     let mut ctx = ClipboardContext::new().map_err(|e| SherlockError {
         error: SherlockErrorType::ClipboardError,
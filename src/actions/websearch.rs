@@ -1,10 +1,13 @@
 use std::collections::HashMap;
+use std::fs;
 use std::process::Command;
 
 use crate::loader::application_loader::{get_applications_dir, get_desktop_files};
-use crate::loader::util::{read_lines, SherlockError, SherlockErrorType};
+use crate::loader::desktop_entry::DesktopFile;
+use crate::loader::util::{SherlockError, SherlockErrorType};
 
-use super::commandlaunch::command_launch;
+use super::commandlaunch::launch;
+use super::exec::ExecContext;
 
 pub fn websearch(engine: &str, query: &str) -> Result<(), SherlockError> {
     let engines: HashMap<&str, &str> = HashMap::from([
@@ -57,25 +60,29 @@ pub fn websearch(engine: &str, query: &str) -> Result<(), SherlockError> {
             traceback: String::new(),
         })?;
     // read default browser desktop file
-    let mut browser = read_lines(browser_file)
-        .map_err(|e| SherlockError {
-            error: SherlockErrorType::FileReadError(browser_file.clone()),
-            traceback: e.to_string(),
-        })?
-        .filter_map(Result::ok)
-        .find(|line| line.starts_with("Exec="))
-        .and_then(|line| line.strip_prefix("Exec=").map(|l| l.to_string()))
+    let content = fs::read_to_string(browser_file).map_err(|e| SherlockError {
+        error: SherlockErrorType::FileReadError(browser_file.clone()),
+        traceback: e.to_string(),
+    })?;
+    let mut browser = DesktopFile::parse(&content)
+        .entry()
+        .and_then(|entry| entry.string("Exec"))
         .ok_or_else(|| SherlockError {
             error: SherlockErrorType::FileParseError(browser_file.clone()),
             traceback: String::new(),
         })?;
 
     let url = url_template.replace("{keyword}", &query.replace(" ", "+"));
-    let command = if browser.contains("%u") {
-        browser.replace("%u", &url)
-    } else {
-        browser.push_str(&format!(" {}", url));
-        browser
+    if !["%u", "%U", "%f", "%F"]
+        .iter()
+        .any(|code| browser.contains(code))
+    {
+        browser.push_str(" %u");
+    }
+    let context = ExecContext {
+        field_codes: true,
+        urls: vec![url],
+        ..Default::default()
     };
-    command_launch(&command, "")
+    launch(&browser, &context)
 }
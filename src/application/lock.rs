@@ -1,15 +1,20 @@
-use std::fs::{self, remove_file, File};
+use std::fs::{self, remove_file, OpenOptions};
 use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
 use std::path::{Path, PathBuf};
 
 use procfs::process::Process;
 
 use crate::daemon::daemon::SherlockDaemon;
+use crate::loader::util::prepare_runtime_dir;
 
-pub fn ensure_single_instance(lock_file: &str) -> Result<LockFile, String> {
-    let path = PathBuf::from(lock_file);
-    if path.exists() {
-        if let Some(content) = fs::read_to_string(&path).ok() {
+pub fn ensure_single_instance(lock_file: &Path) -> Result<LockFile, String> {
+    prepare_runtime_dir(lock_file).map_err(|e| {
+        let (_, message) = e.error.get_message();
+        format!("{}: {}", message, e.traceback)
+    })?;
+    if lock_file.exists() {
+        if let Some(content) = fs::read_to_string(lock_file).ok() {
             if let Some(pid) = content.parse::<i32>().ok() {
                 match Process::new(pid) {
                     Ok(_) => {
@@ -27,20 +32,25 @@ pub fn ensure_single_instance(lock_file: &str) -> Result<LockFile, String> {
 }
 
 pub struct LockFile {
-    path: String,
+    path: PathBuf,
 }
 
 impl LockFile {
-    pub fn new(path: &str) -> Result<Self, String> {
-        if Path::new(path).exists() {
+    pub fn new(path: &Path) -> Result<Self, String> {
+        if path.exists() {
             return Err("Lockfile already exists. Aborting...".to_string());
         }
 
-        match File::create(path) {
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(path)
+        {
             Ok(mut f) => {
                 write!(f, "{}", std::process::id()).map_err(|e| e.to_string())?;
                 Ok(LockFile {
-                    path: path.to_string(),
+                    path: path.to_path_buf(),
                 })
             }
             Err(e) => Err(format!("Failed to create lock file: {}", e)),
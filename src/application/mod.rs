@@ -1,2 +1,3 @@
 pub mod lock;
+pub mod registry;
 pub mod util;
@@ -0,0 +1,116 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::SystemTime;
+
+use crate::launcher::Launcher;
+use crate::loader::application_loader::{get_applications_dir, get_desktop_files};
+use crate::loader::frecency::launch_scores;
+use crate::loader::launcher_loader::{build_launcher, refresh_currency_rates, reprioritize};
+use crate::loader::util::{RawLauncher, SherlockError};
+use crate::loader::Loader;
+use crate::CONFIG;
+
+type Stamps = Vec<(PathBuf, Option<SystemTime>)>;
+
+/// Keeps the launchers of the daemon between openings of the window. Static
+/// launchers are only rebuilt if one of their sources changed, volatile ones
+/// are refreshed on every opening.
+pub struct LauncherRegistry {
+    launchers: Rc<RefCell<Vec<Launcher>>>,
+    sources: Vec<RawLauncher>,
+    stamps: Option<Stamps>,
+    // Non-breaking errors of the last rebuild
+    warnings: Vec<SherlockError>,
+}
+
+impl LauncherRegistry {
+    pub fn new() -> Self {
+        Self {
+            launchers: Rc::new(RefCell::new(Vec::new())),
+            sources: Vec::new(),
+            stamps: None,
+            warnings: Vec::new(),
+        }
+    }
+    /// Forces a full rebuild on the next opening
+    pub fn invalidate(&mut self) {
+        self.stamps = None;
+    }
+    pub fn is_outdated(&self) -> bool {
+        self.stamps.as_ref() != Some(&source_stamps())
+    }
+    pub fn rebuild(
+        &mut self,
+    ) -> Result<(Rc<RefCell<Vec<Launcher>>>, Vec<SherlockError>), SherlockError> {
+        let stamps = source_stamps();
+        let (launchers, n) = Loader::load_launcher_sources()?;
+        let (sources, launchers): (Vec<RawLauncher>, Vec<Launcher>) = launchers.into_iter().unzip();
+
+        self.launchers = Rc::new(RefCell::new(launchers));
+        self.sources = sources;
+        self.stamps = Some(stamps);
+        self.warnings = n.clone();
+        Ok((Rc::clone(&self.launchers), n))
+    }
+    pub fn warnings(&self) -> &[SherlockError] {
+        &self.warnings
+    }
+    /// Reloads the data of volatile launchers and applies the current
+    /// frecency scores to the static ones
+    pub fn refresh(&mut self) -> Vec<SherlockError> {
+        let mut non_breaking: Vec<SherlockError> = Vec::new();
+
+        // Scores decay over time, so they are recomputed on every opening
+        let scores = launch_scores().map_err(|e| non_breaking.push(e)).ok();
+
+        let mut launchers = self.launchers.borrow_mut();
+        for (launcher, raw) in launchers.iter_mut().zip(&self.sources) {
+            if raw.is_volatile() {
+                match build_launcher(raw.clone(), &HashMap::new()) {
+                    Ok(new) => *launcher = new,
+                    Err(e) => non_breaking.push(e),
+                }
+            } else {
+                // Exchange rates expire while the daemon keeps running
+                if raw.r#type == "calculation" {
+                    refresh_currency_rates(raw);
+                }
+                if let Some(scores) = &scores {
+                    reprioritize(launcher, raw, scores);
+                }
+            }
+        }
+        non_breaking
+    }
+}
+
+/// Modification times of everything static launchers are built from
+fn source_stamps() -> Stamps {
+    let modified = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).ok();
+    let mut paths: Vec<PathBuf> = CONFIG.get().map_or_else(Vec::new, |c| {
+        vec![
+            c.files.config.clone(),
+            c.files.fallback.clone(),
+            c.files.alias.clone(),
+            c.files.ignore.clone(),
+        ]
+    });
+    // Desktop files are edited in place, which leaves their directory untouched
+    let app_dirs = get_applications_dir();
+    let mut apps: Vec<PathBuf> = get_desktop_files(app_dirs.clone())
+        .into_iter()
+        .chain(app_dirs)
+        .collect();
+    apps.sort();
+    paths.extend(apps);
+    paths
+        .into_iter()
+        .map(|path| {
+            let stamp = modified(&path);
+            (path, stamp)
+        })
+        .collect()
+}
@@ -2,22 +2,48 @@ use std::cell::RefCell;
 use std::rc::Rc;
 
 use gtk4::Stack;
-use gtk4::{prelude::*, ApplicationWindow};
+use gtk4::{prelude::*, Application, ApplicationWindow};
 
-use crate::loader::util::SherlockError;
+use super::registry::LauncherRegistry;
+use crate::daemon::protocol::{DaemonStatus, ErrorCode, SherlockCommand, SherlockReply};
+use crate::loader::util::{SherlockError, SherlockErrorType};
 use crate::loader::Loader;
 use crate::{ui, CONFIG};
 
+thread_local! {
+    // Errors of the last config reload. Shown until the next reload.
+    static RELOAD_ERRORS: RefCell<(Vec<SherlockError>, Vec<SherlockError>)> =
+        RefCell::new((Vec::new(), Vec::new()));
+    static REGISTRY: RefCell<LauncherRegistry> = RefCell::new(LauncherRegistry::new());
+}
+
 pub fn reload_content(
     window: &ApplicationWindow,
     stack: &Stack,
     stack_page: &Rc<RefCell<String>>,
 ) -> Option<()> {
-    let mut startup_errors: Vec<SherlockError> = Vec::new();
-    let mut non_breaking: Vec<SherlockError> = Vec::new();
-    let app_config = CONFIG.get()?;
+    let (mut startup_errors, mut non_breaking) = RELOAD_ERRORS.with_borrow(|e| e.clone());
+    CONFIG.get()?;
 
-    let (launchers, n) = Loader::load_launchers_sync()
+    // Keep the current pages if only volatile launchers have to be updated
+    let has_search_page = stack.child_by_name("search-page").is_some();
+    if has_search_page && !REGISTRY.with_borrow(|r| r.is_outdated()) {
+        let n = REGISTRY.with_borrow_mut(|r| {
+            let mut n = r.warnings().to_vec();
+            n.extend(r.refresh());
+            n
+        });
+        non_breaking.extend(n);
+        if let Some(next_page) = stack.child_by_name("next-page") {
+            stack.remove(&next_page);
+        }
+        let _ = WidgetExt::activate_action(window, "win.switch-mode", Some(&"all".to_variant()));
+        show_errors(window, stack, stack_page, &startup_errors, &non_breaking);
+        return None;
+    }
+
+    let (launchers, n) = REGISTRY
+        .with_borrow_mut(|r| r.rebuild())
         .map_err(|e| startup_errors.push(e))
         .unwrap_or_default();
     non_breaking.extend(n);
@@ -29,25 +55,152 @@ pub fn reload_content(
     let search_stack = ui::search::search(&launchers, &window, stack_page);
     stack.add_named(&search_stack, Some("search-page"));
 
-    // Logic for the Error-View
-    let error_stack = ui::error_view::errors(&startup_errors, &non_breaking, stack_page);
+    show_errors(window, stack, stack_page, &startup_errors, &non_breaking);
+    None
+}
+
+/// Replaces the error page and shows it if there is anything to report,
+/// otherwise the search page
+fn show_errors(
+    window: &ApplicationWindow,
+    stack: &Stack,
+    stack_page: &Rc<RefCell<String>>,
+    startup_errors: &Vec<SherlockError>,
+    non_breaking: &Vec<SherlockError>,
+) {
+    if let Some(error_page) = stack.child_by_name("error-page") {
+        stack.remove(&error_page);
+    }
+    let error_stack = ui::error_view::errors(startup_errors, non_breaking, stack_page);
     stack.add_named(&error_stack, Some("error-page"));
-    if !app_config.debug.try_suppress_errors {
-        let show_errors = !startup_errors.is_empty();
-        let show_warnings = !app_config.debug.try_suppress_warnings && !non_breaking.is_empty();
-        if show_errors || show_warnings {
-            let _ = gtk4::prelude::WidgetExt::activate_action(
-                window,
-                "win.switch-page",
-                Some(&String::from("error-page").to_variant()),
-            );
-        } else {
-            let _ = gtk4::prelude::WidgetExt::activate_action(
-                window,
-                "win.switch-page",
-                Some(&String::from("search-page").to_variant()),
-            );
+
+    let page = match CONFIG.get() {
+        Some(c) if !c.debug.try_suppress_errors => {
+            let has_errors = !startup_errors.is_empty();
+            let has_warnings = !c.debug.try_suppress_warnings && !non_breaking.is_empty();
+            if has_errors || has_warnings {
+                "error-page"
+            } else {
+                "search-page"
+            }
         }
+        _ => "search-page",
+    };
+    let _ = WidgetExt::activate_action(
+        window,
+        "win.switch-page",
+        Some(&String::from(page).to_variant()),
+    );
+}
+
+/// Re-reads the config and the stylesheet and swaps them in. If the config
+/// can't be parsed, the previous one stays active.
+pub fn reload_config() {
+    let mut startup_errors: Vec<SherlockError> = Vec::new();
+    let mut non_breaking: Vec<SherlockError> = Vec::new();
+
+    let sherlock_flags = Loader::load_flags()
+        .map_err(|e| startup_errors.push(e))
+        .unwrap_or_default();
+    match Loader::load_config(&sherlock_flags) {
+        Ok((mut config, n)) => {
+            let parse_failed = n
+                .iter()
+                .any(|e| matches!(e.error, SherlockErrorType::FileParseError(_)));
+            if parse_failed {
+                startup_errors.extend(n);
+            } else {
+                // These can't change while the daemon is running
+                if let Some(current) = CONFIG.get() {
+                    config.behavior.daemonize = current.behavior.daemonize;
+                    config.files.socket = current.files.socket.clone();
+                    config.files.lock = current.files.lock.clone();
+                }
+                CONFIG.replace(config);
+                non_breaking.extend(n);
+            }
+        }
+        Err(e) => startup_errors.push(e),
     }
-    None
+
+    let n = Loader::load_css()
+        .map_err(|e| startup_errors.push(e))
+        .unwrap_or_default();
+    non_breaking.extend(n);
+
+    RELOAD_ERRORS.set((startup_errors, non_breaking));
+    REGISTRY.with_borrow_mut(|r| r.invalidate());
+}
+
+pub fn handle_daemon_command(
+    application: &Application,
+    window: &ApplicationWindow,
+    stack: &Stack,
+    stack_page: &Rc<RefCell<String>>,
+    command: SherlockCommand,
+) -> SherlockReply {
+    match command {
+        SherlockCommand::Show { mode, query } => {
+            let _ = WidgetExt::activate_action(window, "win.open", None);
+            if let Some(mode) = mode {
+                let _ =
+                    WidgetExt::activate_action(window, "win.switch-mode", Some(&mode.to_variant()));
+                if current_mode(window) != mode.trim() {
+                    return SherlockReply::error(
+                        ErrorCode::UnknownMode,
+                        format!("Mode \"{}\" does not exist", mode),
+                    );
+                }
+            }
+            if let Some(query) = query {
+                let _ =
+                    WidgetExt::activate_action(window, "win.set-search", Some(&query.to_variant()));
+            }
+            SherlockReply::ok(None)
+        }
+        SherlockCommand::Hide => {
+            let _ = WidgetExt::activate_action(window, "win.close", None);
+            SherlockReply::ok(None)
+        }
+        SherlockCommand::Toggle => {
+            let action = if window.is_visible() {
+                "win.close"
+            } else {
+                "win.open"
+            };
+            let _ = WidgetExt::activate_action(window, action, None);
+            SherlockReply::ok(None)
+        }
+        SherlockCommand::Reload => {
+            reload_config();
+            reload_content(window, stack, stack_page);
+            SherlockReply::ok(None)
+        }
+        SherlockCommand::Status => {
+            let status = DaemonStatus {
+                visible: window.is_visible(),
+                mode: current_mode(window),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+            };
+            match serde_json::to_value(status) {
+                Ok(data) => SherlockReply::ok(Some(data)),
+                Err(e) => SherlockReply::error(ErrorCode::Internal, e.to_string()),
+            }
+        }
+        SherlockCommand::Quit => {
+            // Quitting happens after the reply was handed back to the socket
+            let application = application.clone();
+            gtk4::glib::idle_add_local_once(move || application.quit());
+            SherlockReply::ok(None)
+        }
+    }
+}
+
+fn current_mode(window: &ApplicationWindow) -> String {
+    window
+        .lookup_action("switch-mode")
+        .and_then(|action| action.state())
+        .and_then(|state| state.get::<String>())
+        .map(|mode| mode.trim().to_string())
+        .unwrap_or_else(|| String::from("all"))
 }
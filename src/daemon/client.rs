@@ -0,0 +1,131 @@
+use super::daemon::SherlockDaemon;
+use super::protocol::{ReplyStatus, SherlockCommand};
+use crate::loader::util::SherlockErrorType;
+
+// Exit codes of `sherlock msg`
+pub const EXIT_OK: i32 = 0;
+pub const EXIT_COMMAND_FAILED: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+pub const EXIT_NOT_RUNNING: i32 = 3;
+pub const EXIT_IO: i32 = 4;
+
+/// Entry point for `sherlock msg <command> [options]`. Returns the exit code.
+pub fn run(args: &[String]) -> i32 {
+    let command = match parse_args(args) {
+        Ok(command) => command,
+        Err(message) => {
+            eprintln!("{}\n", message);
+            print_usage();
+            return EXIT_USAGE;
+        }
+    };
+
+    match SherlockDaemon::send(&command) {
+        Ok(reply) => {
+            print!("{}", reply.to_line());
+            match reply.status {
+                ReplyStatus::Ok => EXIT_OK,
+                ReplyStatus::Error => EXIT_COMMAND_FAILED,
+            }
+        }
+        Err(e) => {
+            let (name, message) = e.error.get_message();
+            eprintln!("{}: {}\n{}", name, message, e.traceback);
+            match e.error {
+                SherlockErrorType::SocketConnectError(_) => EXIT_NOT_RUNNING,
+                _ => EXIT_IO,
+            }
+        }
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<SherlockCommand, String> {
+    let (name, rest) = args
+        .split_first()
+        .ok_or_else(|| String::from("No command specified."))?;
+
+    let mut mode: Option<String> = None;
+    let mut query: Option<String> = None;
+    let mut iter = rest.iter();
+    while let Some(arg) = iter.next() {
+        let target = match arg.as_str() {
+            // Global flags are already handled by the flag loader
+            "--config" | "--socket" | "--lock" => {
+                iter.next();
+                continue;
+            }
+            "--mode" if name == "show" => &mut mode,
+            "--query" if name == "show" => &mut query,
+            _ => {
+                return Err(format!(
+                    "Unexpected argument \"{}\" for command \"{}\".",
+                    arg, name
+                ))
+            }
+        };
+        let value = iter
+            .next()
+            .ok_or_else(|| format!("Missing value for \"{}\".", arg))?;
+        *target = Some(value.clone());
+    }
+
+    match name.as_str() {
+        "show" => Ok(SherlockCommand::Show { mode, query }),
+        "hide" => Ok(SherlockCommand::Hide),
+        "toggle" => Ok(SherlockCommand::Toggle),
+        "reload" => Ok(SherlockCommand::Reload),
+        "status" => Ok(SherlockCommand::Status),
+        "quit" => Ok(SherlockCommand::Quit),
+        other => Err(format!("Unknown command \"{}\".", other)),
+    }
+}
+
+fn print_usage() {
+    let commands: Vec<(&str, &str)> = vec![
+        (
+            "show",
+            "Open the window. Accepts --mode <alias> and --query <text>.",
+        ),
+        ("hide", "Hide the window."),
+        (
+            "toggle",
+            "Open the window if it is hidden, hide it otherwise.",
+        ),
+        (
+            "reload",
+            "Reload the config, style and launchers of the running daemon.",
+        ),
+        ("status", "Print the state of the running daemon."),
+        ("quit", "Shut down the running daemon."),
+    ];
+    println!("Usage: sherlock msg <command> [options]\n");
+    println!("{:<15} {}", "Command", "Description");
+    for (command, explanation) in commands {
+        println!("{:<15} {}", command, explanation);
+    }
+    println!(
+        "\nExit codes: {} success, {} command failed, {} usage error, {} daemon not running, {} socket error",
+        EXIT_OK, EXIT_COMMAND_FAILED, EXIT_USAGE, EXIT_NOT_RUNNING, EXIT_IO
+    );
+}
+
+#[test]
+fn test_parse_args() {
+    let args = |s: &str| -> Vec<String> { s.split_whitespace().map(String::from).collect() };
+    assert_eq!(parse_args(&args("toggle")), Ok(SherlockCommand::Toggle));
+    assert_eq!(
+        parse_args(&args("show --mode app --query fire")),
+        Ok(SherlockCommand::Show {
+            mode: Some(String::from("app")),
+            query: Some(String::from("fire"))
+        })
+    );
+    assert_eq!(
+        parse_args(&args("hide --socket /tmp/sherlock.socket")),
+        Ok(SherlockCommand::Hide)
+    );
+    assert!(parse_args(&args("show --mode")).is_err());
+    assert!(parse_args(&args("hide --mode app")).is_err());
+    assert!(parse_args(&args("explode")).is_err());
+    assert!(parse_args(&[]).is_err());
+}
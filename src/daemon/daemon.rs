@@ -1,69 +1,207 @@
 use std::os::unix::net::UnixStream;
 
-use crate::loader::util::{SherlockError, SherlockErrorType};
-use crate::SOCKET_PATH;
-use std::io::{Read, Write};
+use crate::loader::util::{default_socket, prepare_runtime_dir, SherlockError, SherlockErrorType};
+use crate::CONFIG;
+use nix::sys::socket::{getsockopt, sockopt::PeerCredentials};
+use nix::sys::stat::{umask, Mode};
+use nix::unistd::getuid;
+use std::fs::{self, Permissions};
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::net::Shutdown;
+use std::os::unix::fs::PermissionsExt;
 use std::os::unix::net::UnixListener;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+use super::protocol::{DaemonRequest, ErrorCode, SherlockCommand, SherlockReply};
+
+// Connections without a command for this long are closed
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
 
 pub struct SherlockDaemon {
-    socket: String,
+    socket: PathBuf,
+    listener: UnixListener,
 }
 impl SherlockDaemon {
-    pub async fn new(pipeline: async_channel::Sender<&str>) -> Self {
-        let _ = std::fs::remove_file(SOCKET_PATH);
-        let listener = UnixListener::bind(SOCKET_PATH).expect("Failed to bind socket");
-        println!("Daemon listening on {}", SOCKET_PATH);
+    /// Binds the socket, which only the current user may connect to
+    pub fn new() -> Result<Self, SherlockError> {
+        let socket = socket_path();
+        prepare_runtime_dir(&socket)?;
+        let _ = fs::remove_file(&socket);
+        let bind_error = |e: std::io::Error| SherlockError {
+            error: SherlockErrorType::SocketBindError(socket.to_string_lossy().to_string()),
+            traceback: e.to_string(),
+        };
 
-        for stream in listener.incoming() {
-            if let Ok(mut stream) = stream {
-                let mut buffer = [0; 1024];
-                match stream.read(&mut buffer) {
-                    Ok(bytes_read) => {
-                        if bytes_read > 0 {
-                            let received_data = String::from_utf8_lossy(&buffer[..bytes_read]);
-                            match received_data.trim() {
-                                "show" => {
-                                    let _ = pipeline.send("open-window").await;
-                                }
-                                _ => println!("Received: {}", received_data),
-                            }
-                            let _ = stream.write_all(b"OK\n");
-                        }
-                    }
-                    Err(e) => {
-                        let _ = stream.write_all(format!("Error encountered: {:?}", e).as_bytes());
-                        eprintln!("Error: {:?}", e)
-                    }
-                }
+        // Created without access for others, so nobody can connect before the chmod
+        let umask_before = umask(Mode::from_bits_truncate(0o177));
+        let listener = UnixListener::bind(&socket);
+        umask(umask_before);
+        let listener = listener.map_err(bind_error)?;
+        fs::set_permissions(&socket, Permissions::from_mode(0o600)).map_err(|e| {
+            let _ = fs::remove_file(&socket);
+            bind_error(e)
+        })?;
+        println!("Daemon listening on {}", socket.display());
+        Ok(Self { socket, listener })
+    }
+    /// Forwards the commands of every connection through `pipeline`. Each
+    /// connection is served on its own thread, so clients that keep their
+    /// stream open don't hold up others.
+    pub fn listen(&self, pipeline: async_channel::Sender<DaemonRequest>) {
+        for mut stream in self.listener.incoming().flatten() {
+            // Only accept commands from processes of the same user
+            if !SherlockDaemon::is_trusted_peer(&stream) {
+                let reply = SherlockReply::error(
+                    ErrorCode::PermissionDenied,
+                    "The daemon belongs to another user",
+                );
+                let _ = stream.write_all(reply.to_line().as_bytes());
+                continue;
             }
+            let pipeline = pipeline.clone();
+            thread::spawn(move || {
+                if let Err(e) = SherlockDaemon::handle_connection(stream, &pipeline) {
+                    eprintln!("Error: {:?}", e)
+                }
+            });
         }
-        Self {
-            socket: SOCKET_PATH.to_string(),
+    }
+    fn is_trusted_peer(stream: &UnixStream) -> bool {
+        getsockopt(stream, PeerCredentials)
+            .is_ok_and(|credentials| credentials.uid() == getuid().as_raw())
+    }
+    fn handle_connection(
+        stream: UnixStream,
+        pipeline: &async_channel::Sender<DaemonRequest>,
+    ) -> std::io::Result<()> {
+        stream.set_read_timeout(Some(IDLE_TIMEOUT))?;
+        let reader = BufReader::new(stream.try_clone()?);
+        let mut writer = stream;
+
+        // Every line holds one command and gets exactly one reply
+        for line in reader.lines() {
+            let line = match line {
+                Ok(line) => line,
+                // Idle connections are closed
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                    return Ok(())
+                }
+                Err(e) => return Err(e),
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let reply = match SherlockCommand::parse(&line) {
+                Ok(command) => {
+                    let (sender, receiver) = async_channel::bounded(1);
+                    let request = DaemonRequest {
+                        command,
+                        reply: sender,
+                    };
+                    match pipeline.send_blocking(request) {
+                        Ok(_) => receiver.recv_blocking().unwrap_or_else(|_| {
+                            SherlockReply::error(ErrorCode::Internal, "No reply from the window")
+                        }),
+                        Err(e) => SherlockReply::error(ErrorCode::Internal, e.to_string()),
+                    }
+                }
+                Err(reply) => reply,
+            };
+            writer.write_all(reply.to_line().as_bytes())?;
         }
+        Ok(())
     }
     fn remove(&self) -> Result<(), SherlockError> {
-        std::fs::remove_file(&self.socket).map_err(|e| SherlockError {
-            error: SherlockErrorType::SocketRemoveError(self.socket.clone()),
+        fs::remove_file(&self.socket).map_err(|e| SherlockError {
+            error: SherlockErrorType::SocketRemoveError(self.socket.to_string_lossy().to_string()),
             traceback: e.to_string(),
         })?;
         Ok(())
     }
     pub fn open() -> Result<(), SherlockError> {
-        let mut stream = UnixStream::connect(SOCKET_PATH).map_err(|e| SherlockError {
-            error: SherlockErrorType::SocketConnectError(SOCKET_PATH.to_string()),
-            traceback: e.to_string(),
-        })?;
-        stream.write_all(b"show").map_err(|e| SherlockError {
-            error: SherlockErrorType::SoecktWriteError(SOCKET_PATH.to_string()),
+        let command = SherlockCommand::Show {
+            mode: None,
+            query: None,
+        };
+        SherlockDaemon::send(&command)?;
+        Ok(())
+    }
+    pub fn send(command: &SherlockCommand) -> Result<SherlockReply, SherlockError> {
+        let socket = socket_path();
+        let socket_str = socket.to_string_lossy().to_string();
+        let mut stream = UnixStream::connect(&socket).map_err(|e| SherlockError {
+            error: SherlockErrorType::SocketConnectError(socket_str.clone()),
             traceback: e.to_string(),
         })?;
+        stream
+            .write_all(command.to_line().as_bytes())
+            .and_then(|_| stream.shutdown(Shutdown::Write))
+            .map_err(|e| SherlockError {
+                error: SherlockErrorType::SoecktWriteError(socket_str.clone()),
+                traceback: e.to_string(),
+            })?;
 
-        Ok(())
+        // Wait for the reply of the daemon
+        let read_error = |e: String| SherlockError {
+            error: SherlockErrorType::SocketReadError(socket_str.clone()),
+            traceback: e,
+        };
+        let mut line = String::new();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .and_then(|_| BufReader::new(&stream).read_line(&mut line))
+            .map_err(|e| read_error(e.to_string()))?;
+        serde_json::from_str(&line).map_err(|e| read_error(e.to_string()))
     }
 }
 
+fn socket_path() -> PathBuf {
+    CONFIG
+        .get()
+        .map_or_else(default_socket, |c| c.files.socket.clone())
+}
+
 impl Drop for SherlockDaemon {
     fn drop(&mut self) {
         let _ = self.remove();
     }
 }
+
+#[test]
+fn test_concurrent_connections() {
+    let socket = std::env::temp_dir().join(format!("sherlock-test-{}.socket", std::process::id()));
+    let _ = fs::remove_file(&socket);
+    let daemon = SherlockDaemon {
+        listener: UnixListener::bind(&socket).unwrap(),
+        socket: socket.clone(),
+    };
+    let (pipeline, requests) = async_channel::unbounded::<DaemonRequest>();
+    thread::spawn(move || daemon.listen(pipeline));
+    thread::spawn(move || {
+        while let Ok(request) = requests.recv_blocking() {
+            let _ = request.reply.send_blocking(SherlockReply::ok(None));
+        }
+    });
+
+    // A client keeping its stream open, like a status bar script
+    let mut idle = UnixStream::connect(&socket).unwrap();
+    idle.write_all(b"status\n").unwrap();
+    let mut line = String::new();
+    BufReader::new(&idle).read_line(&mut line).unwrap();
+    assert!(line.contains("\"ok\""));
+
+    let mut other = UnixStream::connect(&socket).unwrap();
+    other.write_all(b"toggle\n").unwrap();
+    other.shutdown(Shutdown::Write).unwrap();
+    other
+        .set_read_timeout(Some(Duration::from_secs(2)))
+        .unwrap();
+    let mut line = String::new();
+    BufReader::new(&other).read_line(&mut line).unwrap();
+    assert!(line.contains("\"ok\""));
+
+    drop(idle);
+    let _ = fs::remove_file(&socket);
+}
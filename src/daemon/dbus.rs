@@ -0,0 +1,305 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use gio::glib::MainContext;
+use zbus::{fdo, interface, object_server::SignalEmitter, zvariant::Value, Connection};
+
+use super::protocol::{DaemonRequest, DaemonStatus, ReplyStatus, SherlockCommand, SherlockReply};
+
+pub const BUS_NAME: &str = "dev.skxxtz.Sherlock";
+pub const OBJECT_PATH: &str = "/dev/skxxtz/Sherlock";
+pub const INTERFACE: &str = "dev.skxxtz.Sherlock";
+
+static CONNECTION: OnceLock<Connection> = OnceLock::new();
+
+/// Object exported on the session bus. Every call is forwarded through the
+/// same pipeline the Unix socket uses.
+pub struct SherlockService {
+    pipeline: async_channel::Sender<DaemonRequest>,
+}
+
+impl SherlockService {
+    async fn request(&self, command: SherlockCommand) -> fdo::Result<SherlockReply> {
+        let (sender, receiver) = async_channel::bounded(1);
+        let request = DaemonRequest {
+            command,
+            reply: sender,
+        };
+        self.pipeline
+            .send(request)
+            .await
+            .map_err(|e| fdo::Error::Failed(e.to_string()))?;
+        let reply = receiver
+            .recv()
+            .await
+            .map_err(|_| fdo::Error::Failed(String::from("No reply from the window")))?;
+        match reply.status {
+            ReplyStatus::Ok => Ok(reply),
+            ReplyStatus::Error => Err(fdo::Error::Failed(
+                reply.error.map_or_else(String::new, |e| e.message),
+            )),
+        }
+    }
+    async fn status(&self) -> fdo::Result<DaemonStatus> {
+        let reply = self.request(SherlockCommand::Status).await?;
+        reply
+            .data
+            .and_then(|data| serde_json::from_value(data).ok())
+            .ok_or_else(|| fdo::Error::Failed(String::from("Invalid status reply")))
+    }
+}
+
+#[interface(name = "dev.skxxtz.Sherlock")]
+impl SherlockService {
+    /// Opens the window. Empty strings keep the current mode or query.
+    async fn show(&self, mode: &str, query: &str) -> fdo::Result<()> {
+        let non_empty = |s: &str| (!s.is_empty()).then(|| s.to_string());
+        self.request(SherlockCommand::Show {
+            mode: non_empty(mode),
+            query: non_empty(query),
+        })
+        .await?;
+        Ok(())
+    }
+    async fn hide(&self) -> fdo::Result<()> {
+        self.request(SherlockCommand::Hide).await?;
+        Ok(())
+    }
+    async fn toggle(&self) -> fdo::Result<()> {
+        self.request(SherlockCommand::Toggle).await?;
+        Ok(())
+    }
+    async fn reload(&self) -> fdo::Result<()> {
+        self.request(SherlockCommand::Reload).await?;
+        Ok(())
+    }
+
+    #[zbus(property)]
+    async fn visible(&self) -> fdo::Result<bool> {
+        Ok(self.status().await?.visible)
+    }
+    #[zbus(property)]
+    async fn current_mode(&self) -> fdo::Result<String> {
+        Ok(self.status().await?.mode)
+    }
+
+    #[zbus(signal)]
+    async fn result_activated(
+        emitter: &SignalEmitter<'_>,
+        method: &str,
+        value: &str,
+    ) -> zbus::Result<()>;
+}
+
+/// Claims the bus name and exports the service. The connection is kept alive
+/// for the lifetime of the process.
+pub async fn serve(pipeline: async_channel::Sender<DaemonRequest>) -> zbus::Result<()> {
+    let service = SherlockService { pipeline };
+    let connection = zbus::connection::Builder::session()?
+        .name(BUS_NAME)?
+        .serve_at(OBJECT_PATH, service)?
+        .build()
+        .await?;
+    let _ = CONNECTION.set(connection);
+    println!("Daemon registered on the session bus as {}", BUS_NAME);
+    std::future::pending::<()>().await;
+    Ok(())
+}
+
+/// Emits the `ResultActivated` signal if the service is running.
+pub fn emit_result_activated(method: &str, value: &str) {
+    if let Some(connection) = CONNECTION.get() {
+        let connection = connection.clone();
+        let body = (method.to_string(), value.to_string());
+        MainContext::default().spawn_local(async move {
+            let _ = connection
+                .emit_signal(
+                    None::<&str>,
+                    OBJECT_PATH,
+                    INTERFACE,
+                    "ResultActivated",
+                    &body,
+                )
+                .await;
+        });
+    }
+}
+
+/// Emits `PropertiesChanged` for `Visible` if the service is running.
+pub fn emit_visible_changed(visible: bool) {
+    emit_property_changed("Visible", Value::from(visible));
+}
+
+/// Emits `PropertiesChanged` for `CurrentMode` if the service is running.
+pub fn emit_mode_changed(mode: &str) {
+    emit_property_changed("CurrentMode", Value::from(mode.to_string()));
+}
+
+fn emit_property_changed(name: &'static str, value: Value<'static>) {
+    if let Some(connection) = CONNECTION.get() {
+        let connection = connection.clone();
+        MainContext::default().spawn_local(async move {
+            let _ = property_changed(&connection, name, value).await;
+        });
+    }
+}
+
+/// The window state lives outside the service, so the change signal is sent
+/// by hand whenever the window opens, closes or switches modes.
+async fn property_changed(
+    connection: &Connection,
+    name: &str,
+    value: Value<'_>,
+) -> zbus::Result<()> {
+    let changed = HashMap::from([(name, value)]);
+    let invalidated: Vec<&str> = Vec::new();
+    connection
+        .emit_signal(
+            None::<&str>,
+            OBJECT_PATH,
+            "org.freedesktop.DBus.Properties",
+            "PropertiesChanged",
+            &(INTERFACE, changed, invalidated),
+        )
+        .await
+}
+
+/// Serves `interface` at `path` on one end of a peer-to-peer connection and
+/// returns the client end, for testing D-Bus code without a bus. The server
+/// end has to be kept alive for as long as the client is used.
+#[cfg(test)]
+pub fn p2p_connection<I: zbus::object_server::Interface>(
+    path: &'static str,
+    interface: I,
+) -> (zbus::blocking::Connection, zbus::blocking::Connection) {
+    use std::os::unix::net::UnixStream;
+
+    let (server, client) = UnixStream::pair().unwrap();
+    let server = std::thread::spawn(move || {
+        zbus::blocking::connection::Builder::unix_stream(server)
+            .server(zbus::Guid::generate())
+            .unwrap()
+            .p2p()
+            .serve_at(path, interface)
+            .unwrap()
+            .build()
+            .unwrap()
+    });
+    let client = zbus::blocking::connection::Builder::unix_stream(client)
+        .p2p()
+        .build()
+        .unwrap();
+    (client, server.join().unwrap())
+}
+
+#[test]
+fn test_sherlock_service() {
+    use super::protocol::SherlockReply;
+    use std::sync::{Arc, Mutex};
+    use zbus::blocking::{MessageIterator, Proxy};
+    use zbus::message::Type;
+    use zbus::zvariant::OwnedValue;
+
+    // Stands in for the window, answering requests like `handle_daemon_command`
+    let (pipeline, requests) = async_channel::unbounded::<DaemonRequest>();
+    let received: Arc<Mutex<Vec<SherlockCommand>>> = Arc::default();
+    let received_clone = Arc::clone(&received);
+    std::thread::spawn(move || {
+        let (mut visible, mut mode) = (false, String::from("all"));
+        while let Ok(request) = requests.recv_blocking() {
+            let data = match &request.command {
+                SherlockCommand::Show { mode: new_mode, .. } => {
+                    visible = true;
+                    mode = new_mode.clone().unwrap_or(mode);
+                    None
+                }
+                SherlockCommand::Hide => {
+                    visible = false;
+                    None
+                }
+                SherlockCommand::Toggle => {
+                    visible = !visible;
+                    None
+                }
+                SherlockCommand::Status => {
+                    let status = DaemonStatus {
+                        visible,
+                        mode: mode.clone(),
+                        version: String::from("test"),
+                    };
+                    serde_json::to_value(status).ok()
+                }
+                _ => None,
+            };
+            received_clone.lock().unwrap().push(request.command);
+            let _ = request.reply.send_blocking(SherlockReply::ok(data));
+        }
+    });
+
+    let (client, server) = p2p_connection(OBJECT_PATH, SherlockService { pipeline });
+    // Nothing emits changes here, so read every property from the service
+    let proxy: Proxy = zbus::blocking::proxy::Builder::new(&client)
+        .destination(BUS_NAME)
+        .unwrap()
+        .path(OBJECT_PATH)
+        .unwrap()
+        .interface(INTERFACE)
+        .unwrap()
+        .cache_properties(zbus::proxy::CacheProperties::No)
+        .build()
+        .unwrap();
+    let visible = || proxy.get_property::<bool>("Visible").unwrap();
+
+    assert!(!visible());
+    proxy.call_method("Show", &("calc", "")).unwrap();
+    assert!(visible());
+    assert_eq!(proxy.get_property::<String>("CurrentMode").unwrap(), "calc");
+    proxy.call_method("Toggle", &()).unwrap();
+    assert!(!visible());
+    proxy.call_method("Toggle", &()).unwrap();
+    proxy.call_method("Hide", &()).unwrap();
+    assert!(!visible());
+    proxy.call_method("Reload", &()).unwrap();
+
+    let commands: Vec<SherlockCommand> = received
+        .lock()
+        .unwrap()
+        .drain(..)
+        .filter(|command| *command != SherlockCommand::Status)
+        .collect();
+    assert_eq!(
+        commands,
+        vec![
+            SherlockCommand::Show {
+                mode: Some(String::from("calc")),
+                query: None,
+            },
+            SherlockCommand::Toggle,
+            SherlockCommand::Toggle,
+            SherlockCommand::Hide,
+            SherlockCommand::Reload,
+        ]
+    );
+
+    let mut messages = MessageIterator::from(&client);
+    futures::executor::block_on(property_changed(
+        server.inner(),
+        "Visible",
+        Value::from(true),
+    ))
+    .unwrap();
+    let signal = messages
+        .find_map(|message| {
+            let message = message.ok()?;
+            let header = message.header();
+            (header.message_type() == Type::Signal
+                && header.member().is_some_and(|m| m == "PropertiesChanged"))
+            .then_some(message)
+        })
+        .unwrap();
+    let (interface, changed, invalidated): (String, HashMap<String, OwnedValue>, Vec<String>) =
+        signal.body().deserialize().unwrap();
+    assert_eq!(interface, INTERFACE);
+    assert!(bool::try_from(&changed["Visible"]).unwrap());
+    assert!(invalidated.is_empty());
+}
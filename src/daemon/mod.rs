@@ -1 +1,5 @@
+pub mod client;
 pub mod daemon;
+pub mod dbus;
+pub mod protocol;
+pub mod watcher;
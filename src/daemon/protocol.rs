@@ -0,0 +1,203 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub const PROTOCOL_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "command", rename_all = "lowercase")]
+pub enum SherlockCommand {
+    Show {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        mode: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        query: Option<String>,
+    },
+    Hide,
+    Toggle,
+    Reload,
+    Status,
+    Quit,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    InvalidJson,
+    UnsupportedVersion,
+    UnknownCommand,
+    InvalidArgument,
+    UnknownMode,
+    PermissionDenied,
+    Internal,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReplyStatus {
+    Ok,
+    Error,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ReplyError {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SherlockReply {
+    pub version: u32,
+    pub status: ReplyStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<ReplyError>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DaemonStatus {
+    pub visible: bool,
+    pub mode: String,
+    pub version: String,
+}
+
+/// A parsed command together with the channel its reply has to be sent to
+pub struct DaemonRequest {
+    pub command: SherlockCommand,
+    pub reply: async_channel::Sender<SherlockReply>,
+}
+
+impl SherlockCommand {
+    /// Parses a single line received on the socket. Lines that are not JSON objects are
+    /// treated as a bare command name (e.g. `show`) to stay compatible with older clients.
+    pub fn parse(line: &str) -> Result<Self, SherlockReply> {
+        let line = line.trim();
+        let value: Value = if line.starts_with('{') {
+            serde_json::from_str(line)
+                .map_err(|e| SherlockReply::error(ErrorCode::InvalidJson, e.to_string()))?
+        } else {
+            serde_json::json!({ "version": PROTOCOL_VERSION, "command": line })
+        };
+
+        let version = value
+            .get("version")
+            .and_then(Value::as_u64)
+            .unwrap_or(PROTOCOL_VERSION as u64);
+        if version != PROTOCOL_VERSION as u64 {
+            return Err(SherlockReply::error(
+                ErrorCode::UnsupportedVersion,
+                format!(
+                    "Protocol version {} is not supported. Expected version {}",
+                    version, PROTOCOL_VERSION
+                ),
+            ));
+        }
+
+        let command = value
+            .get("command")
+            .and_then(Value::as_str)
+            .ok_or_else(|| {
+                SherlockReply::error(
+                    ErrorCode::InvalidArgument,
+                    "Missing string field \"command\"",
+                )
+            })?;
+        let string_arg = |key: &str| -> Result<Option<String>, SherlockReply> {
+            match value.get(key) {
+                None | Some(Value::Null) => Ok(None),
+                Some(Value::String(s)) => Ok(Some(s.clone())),
+                Some(_) => Err(SherlockReply::error(
+                    ErrorCode::InvalidArgument,
+                    format!("Field \"{}\" has to be a string", key),
+                )),
+            }
+        };
+
+        match command {
+            "show" => Ok(SherlockCommand::Show {
+                mode: string_arg("mode")?,
+                query: string_arg("query")?,
+            }),
+            "hide" => Ok(SherlockCommand::Hide),
+            "toggle" => Ok(SherlockCommand::Toggle),
+            "reload" => Ok(SherlockCommand::Reload),
+            "status" => Ok(SherlockCommand::Status),
+            "quit" => Ok(SherlockCommand::Quit),
+            other => Err(SherlockReply::error(
+                ErrorCode::UnknownCommand,
+                format!("Unknown command \"{}\"", other),
+            )),
+        }
+    }
+    pub fn to_line(&self) -> String {
+        let mut value = serde_json::to_value(self).unwrap_or_default();
+        if let Value::Object(map) = &mut value {
+            map.insert(String::from("version"), PROTOCOL_VERSION.into());
+        }
+        format!("{}\n", value)
+    }
+}
+
+impl SherlockReply {
+    pub fn ok(data: Option<Value>) -> Self {
+        Self {
+            version: PROTOCOL_VERSION,
+            status: ReplyStatus::Ok,
+            error: None,
+            data,
+        }
+    }
+    pub fn error<T: Into<String>>(code: ErrorCode, message: T) -> Self {
+        Self {
+            version: PROTOCOL_VERSION,
+            status: ReplyStatus::Error,
+            error: Some(ReplyError {
+                code,
+                message: message.into(),
+            }),
+            data: None,
+        }
+    }
+    pub fn to_line(&self) -> String {
+        format!("{}\n", serde_json::to_string(self).unwrap_or_default())
+    }
+}
+
+#[test]
+fn test_parse_commands() {
+    assert_eq!(
+        SherlockCommand::parse("show"),
+        Ok(SherlockCommand::Show {
+            mode: None,
+            query: None
+        })
+    );
+    assert_eq!(
+        SherlockCommand::parse(
+            r#"{"version": 1, "command": "show", "mode": "pm", "query": "sus"}"#
+        ),
+        Ok(SherlockCommand::Show {
+            mode: Some(String::from("pm")),
+            query: Some(String::from("sus"))
+        })
+    );
+    let line = SherlockCommand::Toggle.to_line();
+    assert_eq!(SherlockCommand::parse(&line), Ok(SherlockCommand::Toggle));
+
+    let code = |line: &str| {
+        SherlockCommand::parse(line)
+            .unwrap_err()
+            .error
+            .map(|e| e.code)
+    };
+    assert_eq!(code("{\"command\": "), Some(ErrorCode::InvalidJson));
+    assert_eq!(
+        code(r#"{"version": 2, "command": "show"}"#),
+        Some(ErrorCode::UnsupportedVersion)
+    );
+    assert_eq!(code("explode"), Some(ErrorCode::UnknownCommand));
+    assert_eq!(
+        code(r#"{"command": "show", "mode": 3}"#),
+        Some(ErrorCode::InvalidArgument)
+    );
+}
@@ -0,0 +1,113 @@
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+use nix::sys::inotify::{AddWatchFlags, InitFlags, Inotify, WatchDescriptor};
+
+use super::protocol::{DaemonRequest, SherlockCommand};
+use crate::CONFIG;
+
+// Editors often write a file in several steps
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Watches the config, fallback, style, alias and ignore files and asks the
+/// window to reload whenever one of them changes. Blocks until the pipeline
+/// is closed.
+pub fn watch(pipeline: async_channel::Sender<DaemonRequest>) {
+    loop {
+        let (inotify, watches) = match add_watches() {
+            Ok(watch) => watch,
+            Err(e) => {
+                eprintln!("Failed to watch config files: {}", e);
+                return;
+            }
+        };
+
+        // Block until one of the watched files changed
+        loop {
+            match inotify.read_events() {
+                Ok(events) => {
+                    let changed = events.iter().any(|event| {
+                        watches
+                            .get(&event.wd)
+                            .zip(event.name.as_ref())
+                            .map_or(false, |(names, name)| names.contains(name))
+                    });
+                    if changed {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Failed to read file events: {}", e);
+                    return;
+                }
+            }
+        }
+        thread::sleep(DEBOUNCE);
+
+        // Dropping the instance discards the remaining events. The watches are
+        // added again after the reload, as the config might point elsewhere now.
+        drop(inotify);
+        let (sender, receiver) = async_channel::bounded(1);
+        let request = DaemonRequest {
+            command: SherlockCommand::Reload,
+            reply: sender,
+        };
+        if pipeline.send_blocking(request).is_err() {
+            return;
+        }
+        let _ = receiver.recv_blocking();
+    }
+}
+
+fn add_watches() -> nix::Result<(Inotify, HashMap<WatchDescriptor, HashSet<OsString>>)> {
+    let inotify = Inotify::init(InitFlags::IN_CLOEXEC)?;
+    let flags = AddWatchFlags::IN_CLOSE_WRITE
+        | AddWatchFlags::IN_CREATE
+        | AddWatchFlags::IN_DELETE
+        | AddWatchFlags::IN_MOVED_TO
+        | AddWatchFlags::IN_MOVED_FROM;
+
+    // Files get replaced on save by many editors, so their directories are watched
+    let mut dirs: HashMap<PathBuf, HashSet<OsString>> = HashMap::new();
+    for file in watched_files() {
+        if let (Some(dir), Some(name)) = (file.parent(), file.file_name()) {
+            dirs.entry(dir.to_path_buf())
+                .or_default()
+                .insert(name.to_os_string());
+        }
+    }
+
+    let mut watches = HashMap::new();
+    for (dir, names) in dirs {
+        // Missing directories are skipped until the next reload
+        if let Ok(wd) = inotify.add_watch(&dir, flags) {
+            watches.insert(wd, names);
+        }
+    }
+    Ok((inotify, watches))
+}
+
+fn watched_files() -> Vec<PathBuf> {
+    let config = match CONFIG.get() {
+        Some(c) => c,
+        None => return vec![],
+    };
+    let files = &config.files;
+    // The config loader falls back to the other format if the file doesn't exist
+    let mut config_alt = files.config.clone();
+    match files.config.extension().and_then(|e| e.to_str()) {
+        Some("toml") => config_alt.set_extension("json"),
+        _ => config_alt.set_extension("toml"),
+    };
+    vec![
+        files.config.clone(),
+        config_alt,
+        files.fallback.clone(),
+        files.css.clone(),
+        files.alias.clone(),
+        files.ignore.clone(),
+    ]
+}
@@ -1,206 +1,245 @@
+use crate::loader::frecency::unix_now;
 use crate::CONFIG;
 use regex::Regex;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{LazyLock, Mutex};
+
+use super::currency::{format_age, parse_query, rates};
+use super::precise::{self, precision, Number};
+use super::units::{self, Dimension};
+
+// Variables assigned in the calculator and `ans`, kept for the session
+static VARIABLES: Mutex<BTreeMap<String, Number>> = Mutex::new(BTreeMap::new());
+
+// "5 km to mi"
+static UNIT_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)^\s*(?P<value>-?\d+(?:\.\d+)?)\s*(?P<from>\S.*?)\s+(?:in|to|as)\s+(?P<to>\S.*?)\s*$",
+    )
+    .unwrap()
+});
+// "5 km", converted to the unit set in the config
+static PARTIAL_UNIT_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^\s*(?P<value>-?\d+(?:\.\d+)?)\s*(?P<from>\S.*?)\s*$").unwrap()
+});
+// "20 c to f", "70 degrees fahrenheit"
+static TEMPERATURE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^(?P<value>-?\d+(?:\.\d+)?)\s*(?:degrees?|°)?\s*(?P<from>[cfk][a-z]*)\s*(?:to|as|in)?\s*(?:degrees?|°)?\s*(?P<to>[cfk][a-z]*)?$").unwrap()
+});
+// "r = 2.5"
+static ASSIGNMENT_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(?P<name>[a-zA-Z_]\w*)\s*=\s*(?P<expr>[^=].*)$").unwrap());
+
+/// The value of a math expression, and the variable it is assigned to in
+/// expressions like "r = 2.5"
+#[derive(Debug, PartialEq)]
+pub struct MathResult {
+    pub value: Number,
+    pub assignment: Option<String>,
+}
 
 #[derive(Clone, Debug)]
 pub struct Calculator {
     pub capabilities: Option<HashSet<String>>,
 }
 impl Calculator {
-    pub fn measurement(&self, keyword: &str, unit_str: &str) -> Option<String> {
-        let full_pattern = r"(?i)(\d+(?:\.\d+)?)\s*([a-zA-Z]+)\s*(in|to)\s*([a-zA-Z]+)";
-        let full_re = Regex::new(full_pattern).unwrap();
-        if let Some(caps) = full_re.captures(keyword) {
-            let value: f32 = caps[1].parse().ok()?;
-            let from = caps[2].to_lowercase();
-            let to = caps[4].to_lowercase();
-
-            let (factor_from, _) = Measurements::match_unit(&from, unit_str)?;
-            let (factor_to, name) = Measurements::match_unit(&to, unit_str)?;
-
-            let base = self.to_basis(factor_from, value);
-            let res = self.to_unit(factor_to, base);
-            let postfix = if res == 1.0 { "" } else { "s" };
-            return Some(format!("= {:.2} {}{}", res, name, postfix));
+    /// Evaluates math expressions, exactly where possible. Variables take
+    /// precedence over the constants of the config, which take precedence
+    /// over the builtins.
+    pub fn math(&self, keyword: &str) -> Option<MathResult> {
+        let constants = CONFIG
+            .get()
+            .map_or_else(HashMap::new, |c| c.calc.constants.clone());
+        let variables = VARIABLES.lock().ok()?.clone();
+        evaluate_math(keyword, &variables, &constants)
+    }
+    /// Stores `value` as `ans`, and in `variable` if it was assigned
+    pub fn remember(value: Number, variable: Option<&str>) {
+        if let Ok(mut variables) = VARIABLES.lock() {
+            if let Some(variable) = variable {
+                variables.insert(variable.to_string(), value.clone());
+            }
+            variables.insert(String::from("ans"), value);
+        }
+    }
+    /// Converts between units of a dimension enabled in `capabilities`, like
+    /// "5 km to mi". Without a target unit, lengths, weights and volumes are
+    /// converted to the unit set in the config.
+    pub fn measurement(&self, keyword: &str, capabilities: &HashSet<&str>) -> Option<String> {
+        let enabled = |dimension: Dimension| {
+            // Temperatures have their own syntax
+            dimension != Dimension::Temperature
+                && (capabilities.contains("calc.units")
+                    || capabilities.contains(dimension.capability()))
+        };
+        if let Some(caps) = UNIT_RE.captures(keyword) {
+            let value: f64 = caps["value"].parse().ok()?;
+            let (from, to) = units::resolve(&caps["from"], &caps["to"], enabled)?;
+            let res = units::convert(value, from, to);
+            return Some(format!(
+                "= {} {}",
+                Number::Float(res).format(precision()),
+                to.label(res)
+            ));
         }
         // Support for partial ones
-        let part_pattern = r"(?i)(\d+(?:\.\d+)?)\s*([a-zA-Z]+)";
-        let part_re = Regex::new(part_pattern).unwrap();
-        if let Some(caps) = part_re.captures(keyword) {
-            let config = CONFIG.get()?;
-            let value: f32 = caps[1].parse().ok()?;
-            let from = caps[2].to_lowercase();
-            let to = match unit_str {
-                "weights" => config.units.weights.to_lowercase(),
-                "volumes" => config.units.volumes.to_lowercase(),
-                _ => config.units.lengths.to_lowercase(),
-            };
-
-            let (factor_from, _) = Measurements::match_unit(&from, unit_str)?;
-            let (factor_to, name) = Measurements::match_unit(&to, unit_str)?;
-
-            let base = self.to_basis(factor_from, value);
-            let res = self.to_unit(factor_to, base);
-            let postfix = if res == 1.0 { "" } else { "s" };
-            return Some(format!("= {:.2} {}{}", res, name, postfix));
+        let caps = PARTIAL_UNIT_RE.captures(keyword)?;
+        let config = CONFIG.get()?;
+        let value: f64 = caps["value"].parse().ok()?;
+        let (from, to) = units::find(&caps["from"])
+            .into_iter()
+            .filter(|from| enabled(from.dimension))
+            .find_map(|from| {
+                let to = match from.dimension {
+                    Dimension::Length => &config.units.lengths,
+                    Dimension::Weight => &config.units.weights,
+                    Dimension::Volume => &config.units.volumes,
+                    _ => return None,
+                };
+                let to = units::find(to)
+                    .into_iter()
+                    .find(|to| to.dimension == from.dimension)?;
+                Some((from, to))
+            })?;
+        if from == to {
+            return None;
         }
-        None
+        let res = units::convert(value, from, to);
+        Some(format!(
+            "= {} {}",
+            Number::Float(res).format(precision()),
+            to.label(res)
+        ))
     }
     pub fn temperature(&self, keyword: &str) -> Option<String> {
-        let ctof = |c: f32| (c * 9.0 / 5.0) + 32.0;
-        let ftoc = |f: f32| (f - 32.0) * 5.0 / 9.0;
+        // Accepts abbreviations like "c", "cel" or "kelvin"
         let parse_unit = |unit: &str| {
-            if unit == "c" || unit.len() > 1 && "celsius".contains(&unit) {
-                "C"
-            } else if unit == "f" || unit.len() > 1 && "fahrenheit".contains(&unit) {
-                "F"
-            } else {
-                "C"
-            }
+            let unit = unit.to_lowercase();
+            units::UNITS
+                .iter()
+                .filter(|u| u.dimension == Dimension::Temperature)
+                .find(|u| {
+                    u.aliases
+                        .iter()
+                        .any(|a| *a == unit || unit.len() > 1 && a.starts_with(&unit))
+                })
         };
-        let full_pattern = r"(?i)^(?P<value>\d+(?:\.\d+)?)\s*(?:degrees?|°)?\s*(?P<from>(c|f)(elsius|ahrenheit)?)?\s*(?:to|as|in)?\s*(?:degrees?|°)?\s*(?P<to>(c|f)(elsius|ahrenheit)?)?$";
-
-        let full_re = Regex::new(full_pattern).unwrap();
-        match full_re.captures(keyword) {
-            Some(caps) => {
-                let value = caps.name("value")?.as_str().parse::<f32>().ok()?;
-                let from = parse_unit(&caps.name("from")?.as_str().to_lowercase());
-                let to = caps
-                    .name("to")
-                    .map_or(if from == "C" { "F" } else { "C" }, |v| {
-                        parse_unit(v.as_str())
-                    });
-                match to {
-                    "C" => Some(format!("= {} °C", ftoc(value))),
-                    _ => Some(format!("= {} °F", ctof(value))),
-                }
-            }
-            _ => None,
-        }
-    }
-    fn to_basis(&self, factor: f32, value: f32) -> f32 {
-        value * factor
-    }
-    fn to_unit(&self, factor: f32, value: f32) -> f32 {
-        value / factor
+        let caps = TEMPERATURE_RE.captures(keyword)?;
+        let value = caps.name("value")?.as_str().parse::<f64>().ok()?;
+        let from = parse_unit(caps.name("from")?.as_str())?;
+        let to = match caps.name("to") {
+            Some(to) => parse_unit(to.as_str())?,
+            // Celsius and Fahrenheit default to each other, Kelvin to the
+            // configured unit
+            None => match from.symbol {
+                "°C" => parse_unit("f")?,
+                "°F" => parse_unit("c")?,
+                _ => parse_unit(&CONFIG.get()?.units.temperatures)
+                    .filter(|to| to != &from)
+                    .or_else(|| parse_unit("c"))?,
+            },
+        };
+        let res = units::convert(value, from, to);
+        Some(format!(
+            "= {} {}",
+            Number::Float(res).format(precision()),
+            to.symbol
+        ))
     }
-}
-
-enum Measurements {}
-impl Measurements {
-    fn match_unit(unit: &str, unit_str: &str) -> Option<(f32, String)> {
-        match unit_str {
-            "weights" => Weight::match_unit(unit),
-            "volumes" => Volume::match_unit(unit),
-            _ => Length::match_unit(unit),
+    /// The converted amount and the age of the rates used
+    pub fn currency(&self, keyword: &str) -> Option<(String, String)> {
+        let query = parse_query(keyword)?;
+        let rates = rates()?;
+        let to = match query.to {
+            Some(to) => to,
+            None => CONFIG.get()?.units.currency.to_uppercase(),
+        };
+        if to == query.from || !rates.knows(&query.from) {
+            return None;
         }
+        let res = rates.convert(query.value, &query.from, &to)?;
+        let age = format_age(unix_now().saturating_sub(rates.time));
+        Some((
+            // Amounts of money keep their cents
+            format!("= {} {}", Number::Float(res).format(2), to),
+            format!("Rates updated {}", age),
+        ))
     }
 }
 
-pub struct Length;
-impl Length {
-    pub const KILOMETER: f32 = 1000.0;
-    pub const CENTIMETER: f32 = 0.01;
-    pub const MILLIMETER: f32 = 0.001;
-    pub const MICROMETER: f32 = 0.000_001;
-
-    pub const INCH: f32 = 0.0254;
-    pub const FEET: f32 = 0.3048;
-    pub const YARD: f32 = 0.9144;
-    pub const MILE: f32 = 1609.34;
-
-    fn match_unit(unit: &str) -> Option<(f32, String)> {
-        match unit.to_lowercase().as_str() {
-            // Metric units
-            "kilometers" | "kilometer" | "kilos" | "km" => {
-                Some((Length::KILOMETER, String::from("Kilometer")))
-            }
-            "meters" | "meter" | "m" => Some((1.0, String::from("Meter"))),
-            "centimeters" | "centimeter" | "cm" | "cents" => {
-                Some((Length::CENTIMETER, String::from("Centimeter")))
-            }
-            "millimeters" | "millimeter" | "mm" => {
-                Some((Length::MILLIMETER, String::from("Millimeter")))
-            }
-            "micrometers" | "micrometer" | "um" | "µm" => {
-                Some((Length::MICROMETER, String::from("Micrometer")))
-            }
-
-            // Imperial units
-            "inches" | "inch" | "in" => Some((Length::INCH, String::from("Inch"))),
-            "feet" | "foot" | "ft" => Some((Length::FEET, String::from("Feet"))),
-            "yards" | "yard" | "yd" => Some((Length::YARD, String::from("Yard"))),
-            "miles" | "mile" | "mi" => Some((Length::MILE, String::from("Mile"))),
-
-            _ => None,
+fn evaluate_math(
+    keyword: &str,
+    variables: &BTreeMap<String, Number>,
+    constants: &HashMap<String, f64>,
+) -> Option<MathResult> {
+    let keyword = keyword.trim();
+    let (assignment, expr) = match ASSIGNMENT_RE.captures(keyword) {
+        Some(caps) if &caps["name"] != "ans" => {
+            (Some(caps["name"].to_string()), caps.name("expr")?.as_str())
         }
+        Some(_) => return None,
+        None => (None, keyword),
+    };
+    // Plain numbers are not worth a result
+    if assignment.is_none() && keyword.parse::<f64>().is_ok() {
+        return None;
     }
-}
-
-pub struct Weight;
-impl Weight {
-    // Weight units
-    pub const KILOGRAM: f32 = 1.0;
-    pub const GRAM: f32 = 0.001;
-    pub const MILLIGRAM: f32 = 0.000_001;
-    pub const POUND: f32 = 0.453592;
-    pub const OUNCE: f32 = 0.0283495;
-
-    pub const TABLESPOON: f32 = 0.015;
-    pub const TEASPOON: f32 = 0.005;
-    pub const PINCH: f32 = 0.00036;
-    pub const DASH: f32 = 0.0006;
-
-    fn match_unit(unit: &str) -> Option<(f32, String)> {
-        match unit.to_lowercase().as_str() {
-            "kilograms" | "kilogram" | "kg" => Some((Weight::KILOGRAM, String::from("Kilogram"))),
-            "grams" | "gram" | "g" => Some((Weight::GRAM, String::from("Gram"))),
-            "milligrams" | "milligram" | "mg" => {
-                Some((Weight::MILLIGRAM, String::from("Milligram")))
-            }
-            "pounds" | "pound" | "lbs" => Some((Weight::POUND, String::from("Pound"))),
-            "ounces" | "ounce" | "oz" => Some((Weight::OUNCE, String::from("Ounce"))),
-
-            "tablespoons" | "tablespoon" | "tbsp" => {
-                Some((Weight::TABLESPOON, String::from("Tablespoon")))
-            }
-            "teaspoons" | "teaspoon" | "tsp" => Some((Weight::TEASPOON, String::from("Teaspoon"))),
-            "pinch" | "pinches" => Some((Weight::PINCH, String::from("Pinch"))),
-            "dash" | "dashes" => Some((Weight::DASH, String::from("Dash"))),
-
-            _ => None,
+    let lookup = |name: &str| match variables.get(name) {
+        Some(value) => value.exact(),
+        None => constants.get(name).and_then(|c| precise::from_float(*c)),
+    };
+    let value = match precise::evaluate(expr, &lookup) {
+        Some(value) => Number::Exact(value),
+        // Functions, roots and builtin constants need floats
+        None => {
+            let floats: HashMap<String, f64> = variables
+                .iter()
+                .map(|(name, value)| (name.clone(), value.to_f64()))
+                .collect();
+            let expr: meval::Expr = expr.parse().ok()?;
+            let value = expr
+                .eval_with_context((floats, (constants, meval::Context::new())))
+                .ok()?;
+            Number::Float(value)
         }
-    }
+    };
+    Some(MathResult { value, assignment })
 }
-pub struct Volume;
-impl Volume {
-    pub const LITER: f32 = 1.0;
-    pub const MILLILITER: f32 = 0.001;
-    pub const CUBIC_METER: f32 = 1000.0;
-    pub const GALLON: f32 = 3.78541;
-    pub const QUART: f32 = 0.946353;
-    pub const PINT: f32 = 0.473176;
-    pub const CUP: f32 = 0.24;
-    pub const FLUID_OUNCE: f32 = 0.0295735;
-
-    pub fn match_unit(unit: &str) -> Option<(f32, String)> {
-        match unit.to_lowercase().as_str() {
-            "liters" | "liter" | "l" => Some((Volume::LITER, String::from("Liter"))),
-            "milliliters" | "milliliter" | "ml" => {
-                Some((Volume::MILLILITER, String::from("Milliliter")))
-            }
-            "cubicmeters" | "cubicmeter" | "m3" => {
-                Some((Volume::CUBIC_METER, String::from("Cubic Meter")))
-            }
-            "gallons" | "gallon" | "gal" => Some((Volume::GALLON, String::from("Gallon"))),
-            "quarts" | "quart" | "qt" => Some((Volume::QUART, String::from("Quart"))),
-            "pints" | "pint" | "pt" => Some((Volume::PINT, String::from("Pint"))),
-            "cups" | "cup" => Some((Volume::CUP, String::from("Cup"))),
-            "fluidounces" | "fluidounce" | "fl oz" | "oz" => {
-                Some((Volume::FLUID_OUNCE, String::from("Fluid Ounce")))
-            }
 
-            _ => None,
-        }
-    }
+#[test]
+fn test_math() {
+    let variables = BTreeMap::from([
+        (String::from("r"), Number::Float(2.0)),
+        (String::from("ans"), Number::parse("4").unwrap()),
+    ]);
+    let constants = HashMap::from([(String::from("vat"), 0.19), (String::from("r"), 3.0)]);
+    let value =
+        |keyword: &str| evaluate_math(keyword, &variables, &constants).map(|r| r.value.to_f64());
+
+    assert_eq!(value("1 + 2"), Some(3.0));
+    assert_eq!(value("ans * 2"), Some(8.0));
+    assert_eq!(value("100 * (1 + vat)"), Some(119.0));
+    // Variables shadow constants
+    assert_eq!(value("r^2"), Some(4.0));
+    assert!((value("pi*r^2").unwrap() - 12.566).abs() < 1e-3);
+
+    let assigned = evaluate_math("rate = 80 * 1.5", &variables, &constants).unwrap();
+    assert_eq!(assigned.assignment.as_deref(), Some("rate"));
+    assert_eq!(assigned.value, Number::parse("120").unwrap());
+    assert_eq!(value("x = 5"), Some(5.0));
+
+    assert_eq!(value("42"), None);
+    assert_eq!(value("ans = 2"), None);
+    assert_eq!(value("x == 2"), None);
+    assert_eq!(value("unknown + 1"), None);
+
+    // Exact unless floats are needed
+    let exact = |keyword: &str| {
+        evaluate_math(keyword, &variables, &constants)
+            .unwrap()
+            .value
+    };
+    assert_eq!(exact("0.1 + 0.2"), Number::parse("3/10").unwrap());
+    assert_eq!(exact("ans / 3").raw(), "4/3");
+    assert!(matches!(exact("sqrt(r)"), Number::Float(_)));
 }
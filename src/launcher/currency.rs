@@ -0,0 +1,203 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock, RwLock};
+use std::thread;
+
+use crate::loader::frecency::unix_now;
+use crate::loader::util::home_dir;
+
+const RATES_URL: &str = "https://open.er-api.com/v6/latest/EUR";
+
+static RATES: RwLock<Option<Arc<Rates>>> = RwLock::new(None);
+static FETCHING: AtomicBool = AtomicBool::new(false);
+static QUERY_RE: LazyLock<Regex> = LazyLock::new(|| {
+    let currency = r"[$€£¥₹₽₩₺]|[a-zA-Z]{3}";
+    let pattern = format!(
+        r"(?i)^\s*(?P<pre>{c})?\s*(?P<value>\d+(?:\.\d+)?)\s*(?P<from>{c})?\s*(?:(?:in|to|as)\s*(?P<to>{c}))?\s*$",
+        c = currency
+    );
+    Regex::new(&pattern).unwrap()
+});
+
+/// Exchange rates relative to `base`, fetched at the unix time `time`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Rates {
+    pub base: String,
+    pub time: u64,
+    pub rates: HashMap<String, f64>,
+}
+
+impl Rates {
+    pub fn read(path: &Path) -> Option<Self> {
+        let file = File::open(path).ok()?;
+        serde_json::from_reader(file).ok()
+    }
+    fn write(&self, path: &Path) -> Option<()> {
+        fs::create_dir_all(path.parent()?).ok()?;
+        let tmp_path = path.with_extension("tmp");
+        serde_json::to_writer(File::create(&tmp_path).ok()?, self).ok()?;
+        fs::rename(&tmp_path, path).ok()
+    }
+    /// Reads the reply of the rates api
+    pub fn from_response(response: &str) -> Option<Self> {
+        let json: serde_json::Value = serde_json::from_str(response).ok()?;
+        let rates = json["rates"]
+            .as_object()?
+            .iter()
+            .filter_map(|(code, rate)| Some((code.to_uppercase(), rate.as_f64()?)))
+            .collect();
+        Some(Self {
+            base: json["base_code"].as_str()?.to_uppercase(),
+            time: json["time_last_update_unix"].as_u64()?,
+            rates,
+        })
+    }
+    fn rate(&self, code: &str) -> Option<f64> {
+        if code == self.base {
+            return Some(1.0);
+        }
+        self.rates.get(code).copied().filter(|r| *r > 0.0)
+    }
+    pub fn knows(&self, code: &str) -> bool {
+        self.rate(code).is_some()
+    }
+    pub fn convert(&self, value: f64, from: &str, to: &str) -> Option<f64> {
+        Some(value / self.rate(from)? * self.rate(to)?)
+    }
+}
+
+/// An amount of money and the currency it should be converted to, if given
+#[derive(Debug, PartialEq)]
+pub struct CurrencyQuery {
+    pub value: f64,
+    pub from: String,
+    pub to: Option<String>,
+}
+
+/// Reads queries like "100 usd to eur", "50€ in CHF" or "$20"
+pub fn parse_query(keyword: &str) -> Option<CurrencyQuery> {
+    let caps = QUERY_RE.captures(keyword)?;
+    let from = match (caps.name("pre"), caps.name("from")) {
+        (Some(c), None) | (None, Some(c)) => currency_code(c.as_str()),
+        _ => return None,
+    };
+    Some(CurrencyQuery {
+        value: caps["value"].parse().ok()?,
+        from,
+        to: caps.name("to").map(|c| currency_code(c.as_str())),
+    })
+}
+
+fn currency_code(currency: &str) -> String {
+    let code = match currency {
+        "$" => "USD",
+        "€" => "EUR",
+        "£" => "GBP",
+        "¥" => "JPY",
+        "₹" => "INR",
+        "₽" => "RUB",
+        "₩" => "KRW",
+        "₺" => "TRY",
+        code => code,
+    };
+    code.to_uppercase()
+}
+
+/// How long ago the rates were fetched, like "3 hours ago"
+pub fn format_age(seconds: u64) -> String {
+    let (value, unit) = match seconds {
+        s if s < 60 => return String::from("just now"),
+        s if s < 3600 => (s / 60, "minute"),
+        s if s < 86400 => (s / 3600, "hour"),
+        s => (s / 86400, "day"),
+    };
+    let plural = if value == 1 { "" } else { "s" };
+    format!("{} {}{} ago", value, unit, plural)
+}
+
+pub fn rates_path() -> Option<PathBuf> {
+    Some(home_dir().ok()?.join(".cache/sherlock/currency/rates.json"))
+}
+
+/// The cached rates, read from disk on first use
+pub fn rates() -> Option<Arc<Rates>> {
+    if let Some(rates) = RATES.read().ok()?.as_ref() {
+        return Some(Arc::clone(rates));
+    }
+    let rates = Arc::new(Rates::read(&rates_path()?)?);
+    *RATES.write().ok()? = Some(Arc::clone(&rates));
+    Some(rates)
+}
+
+/// Fetches new rates in the background if the cached ones are older than
+/// `interval` minutes. The cached rates stay in use until then, or if the
+/// fetch fails.
+pub fn refresh_rates(interval: u64) {
+    let age = rates().map_or(u64::MAX, |r| unix_now().saturating_sub(r.time));
+    if age < interval * 60 || FETCHING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    thread::spawn(|| {
+        let fetched = reqwest::blocking::get(RATES_URL)
+            .and_then(|response| response.text())
+            .ok()
+            .and_then(|text| Rates::from_response(&text));
+        if let Some(rates) = fetched {
+            if let Some(path) = rates_path() {
+                rates.write(&path);
+            }
+            if let Ok(mut cached) = RATES.write() {
+                *cached = Some(Arc::new(rates));
+            }
+        }
+        FETCHING.store(false, Ordering::SeqCst);
+    });
+}
+
+#[test]
+fn test_currency() {
+    let fixture = include_str!("../../tests/fixtures/currency/rates.json");
+    let rates: Rates = serde_json::from_str(fixture).unwrap();
+    assert_eq!(rates.base, "EUR");
+
+    let query = parse_query("100 usd to eur").unwrap();
+    assert_eq!(
+        query,
+        CurrencyQuery {
+            value: 100.0,
+            from: String::from("USD"),
+            to: Some(String::from("EUR"))
+        }
+    );
+    let eur = rates.convert(query.value, &query.from, "EUR").unwrap();
+    assert!((eur - 100.0 / 1.25).abs() < 1e-9);
+
+    let query = parse_query("50€ in CHF").unwrap();
+    assert_eq!(
+        (query.from.as_str(), query.to.as_deref()),
+        ("EUR", Some("CHF"))
+    );
+    assert!((rates.convert(50.0, "EUR", "CHF").unwrap() - 47.5).abs() < 1e-9);
+
+    // Conversions between two foreign currencies go through the base
+    let gbp = rates.convert(125.0, "USD", "GBP").unwrap();
+    assert!((gbp - 85.0).abs() < 1e-9);
+
+    assert_eq!(parse_query("$20").unwrap().to, None);
+    assert!(parse_query("20").is_none());
+    assert!(parse_query("$20 usd").is_none());
+    assert!(!rates.knows("XYZ"));
+
+    let response = r#"{"result":"success","base_code":"EUR","time_last_update_unix":1700000000,"rates":{"EUR":1,"USD":1.08}}"#;
+    let fetched = Rates::from_response(response).unwrap();
+    assert_eq!(fetched.time, 1700000000);
+    assert!((fetched.convert(1.08, "USD", "EUR").unwrap() - 1.0).abs() < 1e-9);
+
+    assert_eq!(format_age(30), "just now");
+    assert_eq!(format_age(3600), "1 hour ago");
+    assert_eq!(format_age(3 * 86400 + 10), "3 days ago");
+}
@@ -0,0 +1,433 @@
+use chrono::{
+    DateTime, Days, Duration, FixedOffset, Local, Months, NaiveDate, NaiveDateTime, NaiveTime,
+    TimeZone,
+};
+use chrono_tz::Tz;
+use regex::Regex;
+use std::sync::LazyLock;
+
+// "tomorrow", "2026-10-18 15:00 berlin"
+static POINT_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)^(?:(?P<word>now|today|tomorrow|yesterday)|(?P<date>\d{4}-\d{1,2}-\d{1,2})?\s*(?P<time>\d{1,2}:\d{2}(?::\d{2})?)?)\s*(?P<zone>[a-z][a-z_/ ]*)?$",
+    )
+    .unwrap()
+});
+static UNTIL_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^(?P<unit>[a-z]+)\s+(?P<dir>until|till|since)\s+(?P<point>.+)$").unwrap()
+});
+static DIFF_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(?P<a>.+?)\s+-\s+(?P<b>.+)$").unwrap());
+static TARGET_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^(?P<expr>.+?)\s+(?:in|to|as)\s+(?P<target>[a-z][a-z_/ ]*)$").unwrap()
+});
+static OFFSET_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\s*(?P<sign>[+-])\s*(?P<amount>\d+)\s*(?P<unit>[a-z]+)").unwrap()
+});
+
+/// The result of a date or time calculation
+#[derive(Debug, PartialEq)]
+pub enum TimeResult {
+    /// A point in time and the name of its timezone, if it is not the local one
+    Moment(DateTime<FixedOffset>, Option<String>),
+    Date(NaiveDate),
+    /// A length of time, like "67 days"
+    Span(String),
+    Timestamp(i64),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Zone {
+    Local,
+    Named(Tz),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Point {
+    Date(NaiveDate),
+    Moment(DateTime<FixedOffset>, Zone),
+}
+
+/// Everything about the current time the calculations depend on
+struct Clock<Z: TimeZone> {
+    now: DateTime<Z>,
+}
+
+impl<Z: TimeZone> Clock<Z> {
+    fn today(&self, zone: Zone) -> NaiveDate {
+        self.in_zone(&self.now.fixed_offset(), zone).date_naive()
+    }
+    fn localize(&self, naive: NaiveDateTime, zone: Zone) -> Option<DateTime<FixedOffset>> {
+        match zone {
+            Zone::Local => self
+                .now
+                .timezone()
+                .from_local_datetime(&naive)
+                .earliest()
+                .map(|time| time.fixed_offset()),
+            Zone::Named(tz) => tz
+                .from_local_datetime(&naive)
+                .earliest()
+                .map(|time| time.fixed_offset()),
+        }
+    }
+    fn in_zone(&self, time: &DateTime<FixedOffset>, zone: Zone) -> DateTime<FixedOffset> {
+        match zone {
+            Zone::Local => time.with_timezone(&self.now.timezone()).fixed_offset(),
+            Zone::Named(tz) => time.with_timezone(&tz).fixed_offset(),
+        }
+    }
+    /// Dates count as their local midnight where a time is needed
+    fn moment(&self, point: Point) -> Option<(DateTime<FixedOffset>, Zone)> {
+        match point {
+            Point::Date(date) => Some((self.localize(date.into(), Zone::Local)?, Zone::Local)),
+            Point::Moment(time, zone) => Some((time, zone)),
+        }
+    }
+}
+
+/// A timezone by its IANA name or city, like "Europe/Berlin" or "new york"
+fn find_zone(name: &str) -> Option<Zone> {
+    let name = name.trim().to_lowercase().replace(' ', "_");
+    match name.as_str() {
+        "local" => return Some(Zone::Local),
+        "utc" | "gmt" | "z" => return Some(Zone::Named(Tz::UTC)),
+        _ => {}
+    }
+    chrono_tz::TZ_VARIANTS
+        .iter()
+        .find(|tz| {
+            let tz_name = tz.name().to_lowercase();
+            tz_name == name || tz_name.rsplit('/').next() == Some(name.as_str())
+        })
+        .map(|tz| Zone::Named(*tz))
+}
+
+/// Seconds or milliseconds since the epoch, as pasted from logs
+pub fn parse_timestamp(text: &str) -> Option<i64> {
+    let text = text.trim();
+    if !text.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    match text.len() {
+        9 | 10 => text.parse().ok(),
+        12 | 13 => text.parse::<i64>().ok().map(|ms| ms / 1000),
+        _ => None,
+    }
+}
+
+fn parse_point<Z: TimeZone>(text: &str, clock: &Clock<Z>) -> Option<Point> {
+    if let Some(seconds) = parse_timestamp(text) {
+        let time = DateTime::from_timestamp(seconds, 0)?.fixed_offset();
+        return Some(Point::Moment(
+            clock.in_zone(&time, Zone::Local),
+            Zone::Local,
+        ));
+    }
+    let caps = POINT_RE.captures(text.trim())?;
+    let zone = match caps.name("zone") {
+        Some(zone) => find_zone(zone.as_str())?,
+        None => Zone::Local,
+    };
+    if let Some(word) = caps.name("word") {
+        let today = clock.today(zone);
+        return match word.as_str().to_lowercase().as_str() {
+            "now" => Some(Point::Moment(
+                clock.in_zone(&clock.now.fixed_offset(), zone),
+                zone,
+            )),
+            "tomorrow" => today.succ_opt().map(Point::Date),
+            "yesterday" => today.pred_opt().map(Point::Date),
+            _ => Some(Point::Date(today)),
+        };
+    }
+    let date = match caps.name("date") {
+        Some(date) => Some(NaiveDate::parse_from_str(date.as_str(), "%Y-%m-%d").ok()?),
+        None => None,
+    };
+    let time = match caps.name("time") {
+        Some(time) => Some(
+            NaiveTime::parse_from_str(time.as_str(), "%H:%M:%S")
+                .or_else(|_| NaiveTime::parse_from_str(time.as_str(), "%H:%M"))
+                .ok()?,
+        ),
+        None => None,
+    };
+    match (date, time) {
+        (Some(date), None) => Some(Point::Date(date)),
+        (date, Some(time)) => {
+            let date = date.unwrap_or_else(|| clock.today(zone));
+            let moment = clock.localize(date.and_time(time), zone)?;
+            Some(Point::Moment(moment, zone))
+        }
+        (None, None) => None,
+    }
+}
+
+fn add<Z: TimeZone>(clock: &Clock<Z>, point: Point, amount: i64, unit: &str) -> Option<Point> {
+    let seconds = match unit {
+        "s" | "sec" | "secs" | "second" | "seconds" => Some(1),
+        "min" | "mins" | "minute" | "minutes" => Some(60),
+        "h" | "hr" | "hrs" | "hour" | "hours" => Some(3600),
+        _ => None,
+    };
+    // Shorter units move along the timeline
+    if let Some(seconds) = seconds {
+        let (time, zone) = clock.moment(point)?;
+        let time = time.checked_add_signed(Duration::try_seconds(amount.checked_mul(seconds)?)?)?;
+        return Some(Point::Moment(clock.in_zone(&time, zone), zone));
+    }
+    // Days and longer keep the time of day
+    let shift = |date: NaiveDate| -> Option<NaiveDate> {
+        let count = amount.unsigned_abs();
+        let (days, months) = match unit {
+            "d" | "day" | "days" => (count, 0),
+            "w" | "wk" | "week" | "weeks" => (count.checked_mul(7)?, 0),
+            "mo" | "month" | "months" => (0, count),
+            "y" | "yr" | "year" | "years" => (0, count.checked_mul(12)?),
+            _ => return None,
+        };
+        let days = Days::new(days);
+        let months = Months::new(u32::try_from(months).ok()?);
+        if amount < 0 {
+            date.checked_sub_days(days)?.checked_sub_months(months)
+        } else {
+            date.checked_add_days(days)?.checked_add_months(months)
+        }
+    };
+    match point {
+        Point::Date(date) => shift(date).map(Point::Date),
+        Point::Moment(time, zone) => {
+            let naive = time.naive_local();
+            let date = shift(naive.date())?;
+            let time = clock.localize(date.and_time(naive.time()), zone)?;
+            Some(Point::Moment(time, zone))
+        }
+    }
+}
+
+fn plural(amount: i64, unit: &str) -> String {
+    match amount.abs() {
+        1 => format!("{} {}", amount, unit),
+        _ => format!("{} {}s", amount, unit),
+    }
+}
+
+/// A duration in days, hours and minutes, leaving out empty parts
+fn humanize(duration: Duration) -> String {
+    let sign = if duration < Duration::zero() { "-" } else { "" };
+    let duration = duration.abs();
+    let parts: Vec<String> = [
+        (duration.num_days(), "day"),
+        (duration.num_hours() % 24, "hour"),
+        (duration.num_minutes() % 60, "minute"),
+    ]
+    .iter()
+    .filter(|(amount, _)| *amount != 0)
+    .map(|(amount, unit)| plural(*amount, unit))
+    .collect();
+    if parts.is_empty() {
+        format!("{}{}", sign, plural(duration.num_seconds(), "second"))
+    } else {
+        format!("{}{}", sign, parts.join(" "))
+    }
+}
+
+/// The time from `from` to `to`, in `unit` if given
+fn span<Z: TimeZone>(
+    clock: &Clock<Z>,
+    from: Point,
+    to: Point,
+    unit: Option<&str>,
+) -> Option<String> {
+    let unit = unit.map(|u| u.to_lowercase());
+    let days = match (&from, &to) {
+        (Point::Date(from), Point::Date(to)) => Some((*to - *from).num_days()),
+        _ => None,
+    };
+    let duration = match days {
+        Some(days) => Duration::try_days(days)?,
+        None => clock.moment(to)?.0 - clock.moment(from)?.0,
+    };
+    let span = match unit.as_deref() {
+        None if days.is_some() => plural(duration.num_days(), "day"),
+        None => humanize(duration),
+        Some("seconds") => plural(duration.num_seconds(), "second"),
+        Some("minutes") => plural(duration.num_minutes(), "minute"),
+        Some("hours") => plural(duration.num_hours(), "hour"),
+        Some("days") => plural(duration.num_days(), "day"),
+        Some("weeks") => {
+            let weeks = plural(duration.num_days() / 7, "week");
+            match duration.num_days() % 7 {
+                0 => weeks,
+                rest => format!("{} {}", weeks, plural(rest.abs(), "day")),
+            }
+        }
+        _ => return None,
+    };
+    Some(span)
+}
+
+fn evaluate_with<Z: TimeZone>(keyword: &str, clock: &Clock<Z>) -> Option<TimeResult> {
+    let keyword = keyword.trim();
+
+    // "days until 2026-12-24", "hours since 08:00"
+    if let Some(caps) = UNTIL_RE.captures(keyword) {
+        let point = parse_point(&caps["point"], clock)?;
+        let now = match (&point, caps["unit"].to_lowercase().as_str()) {
+            (Point::Date(_), "days" | "weeks") => Point::Date(clock.today(Zone::Local)),
+            _ => Point::Moment(clock.now.fixed_offset(), Zone::Local),
+        };
+        let (from, to) = match caps["dir"].to_lowercase().as_str() {
+            "since" => (point, now),
+            _ => (now, point),
+        };
+        return span(clock, from, to, Some(&caps["unit"])).map(TimeResult::Span);
+    }
+
+    // "2026-10-18 - 2026-03-01"
+    // Large numbers on both sides stay a subtraction for the calculator
+    if let Some(caps) = DIFF_RE
+        .captures(keyword)
+        .filter(|c| parse_timestamp(&c["a"]).is_none() || parse_timestamp(&c["b"]).is_none())
+    {
+        if let (Some(a), Some(b)) = (
+            parse_point(&caps["a"], clock),
+            parse_point(&caps["b"], clock),
+        ) {
+            return span(clock, b, a, None).map(TimeResult::Span);
+        }
+    }
+
+    // "now + 3 weeks", "15:00 berlin in tokyo", "1718000000 to date"
+    let (expr, target) = match TARGET_RE.captures(keyword) {
+        Some(caps) => (
+            caps.name("expr")?.as_str(),
+            Some(caps["target"].to_lowercase()),
+        ),
+        None => (keyword, None),
+    };
+    let first_offset = OFFSET_RE.find(expr).map_or(expr.len(), |m| m.start());
+    let (base, offsets) = expr.split_at(first_offset);
+    let mut point = parse_point(base, clock)?;
+    let mut rest = offsets;
+    while !rest.is_empty() {
+        let caps = OFFSET_RE
+            .captures(rest)
+            .filter(|c| c.get(0).is_some_and(|m| m.start() == 0))?;
+        let amount: i64 = caps["amount"].parse().ok()?;
+        let amount = if &caps["sign"] == "-" {
+            -amount
+        } else {
+            amount
+        };
+        point = add(clock, point, amount, &caps["unit"].to_lowercase())?;
+        rest = &rest[caps.get(0)?.end()..];
+    }
+    // A bare timestamp is only read as one when converted
+    if target.is_none() && offsets.is_empty() && parse_timestamp(base).is_some() {
+        return None;
+    }
+
+    let point = match target.as_deref() {
+        None | Some("date") | Some("time") => point,
+        Some("unix") | Some("timestamp") | Some("epoch") => {
+            return Some(TimeResult::Timestamp(clock.moment(point)?.0.timestamp()));
+        }
+        Some(zone) => {
+            let zone = find_zone(zone)?;
+            let (time, _) = clock.moment(point)?;
+            Point::Moment(clock.in_zone(&time, zone), zone)
+        }
+    };
+    Some(match point {
+        Point::Date(date) => TimeResult::Date(date),
+        Point::Moment(time, Zone::Local) => TimeResult::Moment(time, None),
+        Point::Moment(time, Zone::Named(tz)) => {
+            TimeResult::Moment(time, Some(tz.name().to_string()))
+        }
+    })
+}
+
+/// Evaluates date and time calculations like "now + 3 weeks",
+/// "days until 2026-12-24", "15:00 berlin in tokyo", "1718000000 to date"
+/// or "2026-10-18 - 2026-03-01"
+pub fn evaluate(keyword: &str) -> Option<TimeResult> {
+    evaluate_with(keyword, &Clock { now: Local::now() })
+}
+
+/// The local time of a timestamp, like one pasted from the clipboard
+pub fn from_timestamp(text: &str) -> Option<TimeResult> {
+    let time = DateTime::from_timestamp(parse_timestamp(text)?, 0)?;
+    Some(TimeResult::Moment(
+        time.with_timezone(&Local).fixed_offset(),
+        None,
+    ))
+}
+
+#[test]
+fn test_time_calculations() {
+    use chrono_tz::Europe::Berlin;
+
+    // Sunday, 2026-10-18 12:00 in Berlin, two weeks before the end of summer time
+    let clock = Clock {
+        now: Berlin.with_ymd_and_hms(2026, 10, 18, 12, 0, 0).unwrap(),
+    };
+    let eval = |keyword: &str| evaluate_with(keyword, &clock);
+    let moment = |result: Option<TimeResult>| match result {
+        Some(TimeResult::Moment(time, zone)) => (time.to_rfc3339(), zone),
+        other => panic!("not a moment: {:?}", other),
+    };
+    let date = |y, m, d| Some(TimeResult::Date(NaiveDate::from_ymd_opt(y, m, d).unwrap()));
+    let span = |s: &str| Some(TimeResult::Span(String::from(s)));
+
+    // Days keep the time of day across the change to winter time
+    assert_eq!(
+        moment(eval("now + 3 weeks")),
+        (String::from("2026-11-08T12:00:00+01:00"), None)
+    );
+    assert_eq!(
+        moment(eval("now - 2 hours + 30 min")),
+        (String::from("2026-10-18T10:30:00+02:00"), None)
+    );
+    assert_eq!(eval("2026-01-31 + 1 month"), date(2026, 2, 28));
+    assert_eq!(eval("tomorrow"), date(2026, 10, 19));
+
+    assert_eq!(eval("days until 2026-12-24"), span("67 days"));
+    assert_eq!(eval("weeks until 2026-12-24"), span("9 weeks 4 days"));
+    assert_eq!(eval("hours since 08:00"), span("4 hours"));
+    assert_eq!(eval("2026-10-18 - 2026-03-01"), span("231 days"));
+    assert_eq!(
+        eval("2026-10-18 14:30 - 2026-10-17 12:00"),
+        span("1 day 2 hours 30 minutes")
+    );
+
+    assert_eq!(
+        moment(eval("15:00 berlin in tokyo")),
+        (
+            String::from("2026-10-18T22:00:00+09:00"),
+            Some(String::from("Asia/Tokyo"))
+        )
+    );
+    assert_eq!(
+        moment(eval("9:00 new york to utc")),
+        (
+            String::from("2026-10-18T13:00:00+00:00"),
+            Some(String::from("UTC"))
+        )
+    );
+    assert_eq!(
+        moment(eval("1718000000 to date")),
+        (String::from("2024-06-10T08:13:20+02:00"), None)
+    );
+    assert_eq!(
+        eval("2026-10-18 12:00 utc to unix"),
+        Some(TimeResult::Timestamp(1792324800))
+    );
+
+    assert_eq!(parse_timestamp("1718000000000"), Some(1718000000));
+    assert_eq!(parse_timestamp("12345"), None);
+    assert_eq!(eval("1718000000"), None);
+    assert_eq!(eval("now in atlantis"), None);
+    assert_eq!(eval("2 + 2"), None);
+}
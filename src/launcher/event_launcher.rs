@@ -24,7 +24,8 @@ pub struct EventLauncher {
 
 impl EventLauncher {
     pub fn get_event(date: &str, event_start: &str, event_end: &str) -> Option<TeamsEvent> {
-        let calendar_client = CONFIG.get()?.default_apps.calendar_client.as_ref();
+        let config = CONFIG.get()?;
+        let calendar_client = config.default_apps.calendar_client.as_ref();
         match calendar_client {
             "thunderbird" => {
                 let thunderbird_manager = ThunderBirdEventManager::new();
@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::HashMap;
 
 use gtk4::Box;
 
@@ -8,9 +8,14 @@ pub mod bulk_text_launcher;
 pub mod calc_launcher;
 pub mod category_launcher;
 pub mod clipboard_launcher;
+pub mod currency;
+pub mod datetime;
 pub mod event_launcher;
+pub mod precise;
 pub mod process_launcher;
+pub mod programmer;
 pub mod system_cmd_launcher;
+pub mod units;
 mod utils;
 pub mod weather_launcher;
 pub mod web_launcher;
@@ -68,10 +73,32 @@ pub struct Launcher {
 #[derive(Clone, Debug)]
 pub struct ResultItem {
     pub priority: f32,
+    /// How well the result matches the search between 0 and 1
+    pub relevance: f32,
+    /// Key the launch is counted under, if the result is launchable
+    pub exec: Option<String>,
     pub row_item: SherlockRow,
     pub shortcut_holder: Option<Box>,
 }
 
+// How many priority levels a perfect match can climb
+const RELEVANCE_WEIGHT: f32 = 1.0;
+// How many priority levels results picked for the query before can climb
+const SELECTION_WEIGHT: f32 = 1.0;
+
+impl ResultItem {
+    /// Sort key combining the priority with the search relevance. Lower ranks first.
+    pub fn rank(&self) -> f32 {
+        self.priority - self.relevance * RELEVANCE_WEIGHT
+    }
+    /// Lifts the result by how often it was picked for the current query
+    pub fn apply_selection_boost(&mut self, boosts: &HashMap<String, f32>) {
+        if let Some(boost) = self.exec.as_ref().and_then(|exec| boosts.get(exec)) {
+            self.priority -= boost / (boost + 1.0) * SELECTION_WEIGHT;
+        }
+    }
+}
+
 impl Launcher {
     // TODO: tile method recreates already stored data...
     pub fn get_patch(&self, keyword: &str) -> Vec<ResultItem> {
@@ -90,42 +117,11 @@ impl Launcher {
             _ => Vec::new(),
         }
     }
-    pub fn get_execs(&self) -> Option<HashSet<String>> {
-        // NOTE: make a function to check for exec changes in the caching algorithm
+    /// Tiles shown once the mode of the launcher is opened, before anything is typed
+    pub fn get_mode_patch(&self) -> Vec<ResultItem> {
         match &self.launcher_type {
-            LauncherType::App(app) => {
-                let execs: HashSet<String> =
-                    app.apps.iter().map(|(_, v)| v.exec.to_string()).collect();
-                Some(execs)
-            }
-            LauncherType::Web(web) => {
-                let exec = format!("websearch-{}", web.engine);
-                let execs: HashSet<String> = HashSet::from([(exec)]);
-                Some(execs)
-            }
-            LauncherType::SystemCommand(cmd) => {
-                let execs: HashSet<String> = cmd
-                    .commands
-                    .iter()
-                    .map(|(_, v)| v.exec.to_string())
-                    .collect();
-                Some(execs)
-            }
-            LauncherType::CategoryLauncher(ctg) => {
-                let execs: HashSet<String> = ctg
-                    .categories
-                    .iter()
-                    .map(|(_, v)| v.exec.to_string())
-                    .collect();
-                Some(execs)
-            }
-
-            // None-Home Launchers
-            LauncherType::Calc(_) => None,
-            LauncherType::BulkText(_) => None,
-            LauncherType::Clipboard(_) => None,
-            LauncherType::EventLauncher(_) => None,
-            _ => None,
+            LauncherType::Calc(calc) => Tile::calc_history_tile(self, &calc),
+            _ => Vec::new(),
         }
     }
     pub fn get_loader_widget(self, keyword: &str) -> Option<AsyncLauncherTile> {
@@ -172,6 +168,9 @@ pub fn construct_tiles(keyword: &str, launchers: &[Launcher], mode: &str) -> Vec
             let result = launcher.get_patch(keyword);
             results.extend(result);
         }
+        if launcher.alias.as_deref() == Some(sel_mode) && keyword.trim().is_empty() {
+            results.extend(launcher.get_mode_patch());
+        }
     }
     results
 }
@@ -0,0 +1,445 @@
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{Pow, Signed, ToPrimitive, Zero};
+use std::env;
+
+use crate::loader::util::default_calc_precision;
+use crate::CONFIG;
+
+// Like in JavaScript, numbers outside of this range are shown in scientific
+// notation
+const SCIENTIFIC_ABOVE: i32 = 21;
+const SCIENTIFIC_BELOW: i32 = -7;
+// Powers and exponents whose result would exceed this many bits are left to
+// floats
+const MAX_POWER_BITS: u64 = 100_000;
+
+/// A calculator value. Expressions of numbers, variables and the basic
+/// operators are calculated exactly, everything else as a float.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Number {
+    Exact(BigRational),
+    Float(f64),
+}
+
+impl Number {
+    /// Reads values written by `raw`
+    pub fn parse(raw: &str) -> Option<Self> {
+        if let Some((numer, denom)) = raw.split_once('/') {
+            let numer: BigInt = numer.trim().parse().ok()?;
+            let denom: BigInt = denom.trim().parse().ok()?;
+            if denom.is_zero() {
+                return None;
+            }
+            return Some(Number::Exact(BigRational::new(numer, denom)));
+        }
+        decimal(raw)
+            .map(Number::Exact)
+            .or_else(|| raw.parse().ok().map(Number::Float))
+    }
+    /// The value without any loss, e.g. "1/3"
+    pub fn raw(&self) -> String {
+        match self {
+            Number::Exact(r) => r.to_string(),
+            Number::Float(f) => f.to_string(),
+        }
+    }
+    pub fn exact(&self) -> Option<BigRational> {
+        match self {
+            Number::Exact(r) => Some(r.clone()),
+            Number::Float(f) => from_float(*f),
+        }
+    }
+    pub fn to_f64(&self) -> f64 {
+        match self {
+            Number::Exact(r) => r.to_f64().unwrap_or(f64::NAN),
+            Number::Float(f) => *f,
+        }
+    }
+    /// The value rounded to `precision` decimal places, formatted for the
+    /// locale
+    pub fn format(&self, precision: usize) -> String {
+        self.format_with(precision, &locale_separators())
+    }
+    pub fn format_with(&self, precision: usize, separators: &Separators) -> String {
+        let value = match self {
+            Number::Exact(r) => r.clone(),
+            Number::Float(f) => match BigRational::from_float(*f) {
+                Some(r) => r,
+                None => return f.to_string(),
+            },
+        };
+        format_rational(&value, precision, separators)
+    }
+    /// The exact fraction, if the formatted value had to be rounded
+    pub fn fraction(&self, precision: usize) -> Option<String> {
+        let Number::Exact(r) = self else {
+            return None;
+        };
+        let scaled = r * pow10(precision as i32);
+        let short = r.denom().bits() <= 32 && r.numer().bits() <= 64;
+        (!scaled.is_integer() && short).then(|| r.to_string())
+    }
+}
+
+/// Number of decimal places results are rounded to
+pub fn precision() -> usize {
+    CONFIG
+        .get()
+        .map_or_else(default_calc_precision, |c| c.calc.precision)
+}
+
+/// The characters grouping thousands and separating decimals
+#[derive(Debug, PartialEq)]
+pub struct Separators {
+    pub group: &'static str,
+    pub decimal: char,
+}
+
+/// Separators of the locale in `LC_ALL`, `LC_NUMERIC` or `LANG`
+pub fn locale_separators() -> Separators {
+    let locale = ["LC_ALL", "LC_NUMERIC", "LANG"]
+        .iter()
+        .filter_map(|var| env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_default();
+    separators(&locale)
+}
+
+/// Separators for locale names like "de_DE.UTF-8". Unknown locales use the
+/// English ones.
+pub fn separators(locale: &str) -> Separators {
+    let mut parts = locale.split(['_', '.', '@']);
+    let language = parts.next().unwrap_or_default();
+    let region = parts.next().unwrap_or_default();
+    let (group, decimal) = match (language, region) {
+        (_, "CH") | (_, "LI") => ("'", '.'),
+        ("es", "MX" | "US") => (",", '.'),
+        (
+            "de" | "nl" | "da" | "id" | "it" | "es" | "pt" | "tr" | "el" | "ro" | "sl" | "hr"
+            | "sr" | "is" | "vi",
+            _,
+        ) => (".", ','),
+        (
+            "fr" | "ru" | "uk" | "pl" | "cs" | "sk" | "fi" | "sv" | "nb" | "nn" | "no" | "hu"
+            | "bg" | "et" | "lt" | "lv" | "be" | "kk",
+            _,
+        ) => ("\u{a0}", ','),
+        _ => (",", '.'),
+    };
+    Separators { group, decimal }
+}
+
+fn pow10(exponent: i32) -> BigRational {
+    let power = BigRational::from_integer(BigInt::from(10).pow(exponent.unsigned_abs()));
+    match exponent >= 0 {
+        true => power,
+        false => power.recip(),
+    }
+}
+
+/// The exponent of the highest digit of a positive value
+fn magnitude(value: &BigRational) -> i32 {
+    let mut exponent =
+        value.numer().to_string().len() as i32 - value.denom().to_string().len() as i32;
+    while pow10(exponent) > *value {
+        exponent -= 1;
+    }
+    while pow10(exponent + 1) <= *value {
+        exponent += 1;
+    }
+    exponent
+}
+
+fn format_rational(value: &BigRational, precision: usize, separators: &Separators) -> String {
+    if value.is_zero() {
+        return String::from("0");
+    }
+    let sign = if value.is_negative() { "-" } else { "" };
+    let abs = value.abs();
+    let exponent = magnitude(&abs);
+    if !(SCIENTIFIC_BELOW..SCIENTIFIC_ABOVE).contains(&exponent) {
+        let mut mantissa = fixed(&(&abs / pow10(exponent)), precision);
+        let mut exponent = exponent;
+        // Rounding may carry over into another digit, as in 9.99 -> 10
+        if mantissa.0.len() > 1 {
+            mantissa = fixed(&(&abs / pow10(exponent + 1)), precision);
+            exponent += 1;
+        }
+        let (int, frac) = mantissa;
+        return match frac.is_empty() {
+            true => format!("{}{}e{}", sign, int, exponent),
+            false => format!("{}{}{}{}e{}", sign, int, separators.decimal, frac, exponent),
+        };
+    }
+    let (int, frac) = fixed(&abs, precision);
+    if int == "0" && frac.is_empty() {
+        return String::from("0");
+    }
+    let grouped = group(&int, separators.group);
+    match frac.is_empty() {
+        true => format!("{}{}", sign, grouped),
+        false => format!("{}{}{}{}", sign, grouped, separators.decimal, frac),
+    }
+}
+
+/// Integer and decimal digits of a positive value, rounded to `precision`
+/// places and without trailing zeros
+fn fixed(value: &BigRational, precision: usize) -> (String, String) {
+    let scaled = (value * pow10(precision as i32)).round().to_integer();
+    let digits = format!("{:0>width$}", scaled, width = precision + 1);
+    let (int, frac) = digits.split_at(digits.len() - precision);
+    (int.to_string(), frac.trim_end_matches('0').to_string())
+}
+
+fn group(digits: &str, separator: &str) -> String {
+    let mut grouped = String::with_capacity(digits.len() * 2);
+    // Groups of three counted from the right
+    let offset = digits.len() % 3;
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && i % 3 == offset {
+            grouped.push_str(separator);
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+/// Reads decimal numbers like "-12.5" or "1e-3" exactly
+fn decimal(literal: &str) -> Option<BigRational> {
+    let (negative, literal) = match literal.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, literal),
+    };
+    let (mantissa, exponent) = match literal.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse::<i32>().ok()?),
+        None => (literal, 0),
+    };
+    let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    if int.is_empty() && frac.is_empty()
+        || !int.chars().chain(frac.chars()).all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+    // A power of ten takes less than 4 bits per digit
+    let exponent = exponent as i64 - frac.len() as i64;
+    if exponent.unsigned_abs().saturating_mul(4) > MAX_POWER_BITS {
+        return None;
+    }
+    let digits: BigInt = format!("{}{}", int, frac).parse().ok()?;
+    let value = BigRational::from_integer(digits) * pow10(exponent as i32);
+    Some(if negative { -value } else { value })
+}
+
+/// The decimal a float is shown as, so that 0.1 stays 1/10
+pub fn from_float(value: f64) -> Option<BigRational> {
+    if !value.is_finite() {
+        return None;
+    }
+    decimal(&value.to_string())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(BigRational),
+    Ident(String),
+    Op(char),
+    Open,
+    Close,
+}
+
+fn tokenize(expr: &str) -> Option<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let chars: Vec<char> = expr.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || c == '.' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            // Exponents like "1e-3", but not the constant in "2e"
+            if i + 1 < chars.len() && matches!(chars[i], 'e' | 'E') {
+                let sign = matches!(chars[i + 1], '+' | '-') as usize;
+                if chars.get(i + 1 + sign).is_some_and(|c| c.is_ascii_digit()) {
+                    i += 1 + sign;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+            let literal: String = chars[start..i].iter().collect();
+            tokens.push(Token::Number(decimal(&literal)?));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        } else {
+            tokens.push(match c {
+                '+' | '-' | '*' | '/' | '%' | '^' => Token::Op(c),
+                '(' => Token::Open,
+                ')' => Token::Close,
+                _ => return None,
+            });
+            i += 1;
+        }
+    }
+    Some(tokens)
+}
+
+struct Parser<'a, F: Fn(&str) -> Option<BigRational>> {
+    tokens: Vec<Token>,
+    pos: usize,
+    lookup: &'a F,
+}
+
+impl<F: Fn(&str) -> Option<BigRational>> Parser<'_, F> {
+    fn next_op(&mut self, ops: &[char]) -> Option<char> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Op(op)) if ops.contains(op) => {
+                self.pos += 1;
+                Some(*op)
+            }
+            _ => None,
+        }
+    }
+    fn expr(&mut self) -> Option<BigRational> {
+        let mut value = self.term()?;
+        while let Some(op) = self.next_op(&['+', '-']) {
+            let rhs = self.term()?;
+            value = match op {
+                '+' => value + rhs,
+                _ => value - rhs,
+            };
+        }
+        Some(value)
+    }
+    fn term(&mut self) -> Option<BigRational> {
+        let mut value = self.unary()?;
+        while let Some(op) = self.next_op(&['*', '/', '%']) {
+            let rhs = self.unary()?;
+            value = match op {
+                '*' => value * rhs,
+                _ if rhs.is_zero() => return None,
+                '/' => value / rhs,
+                // Truncated like the remainder of floats
+                _ => &value - &rhs * (&value / &rhs).trunc(),
+            };
+        }
+        Some(value)
+    }
+    fn unary(&mut self) -> Option<BigRational> {
+        match self.next_op(&['-', '+']) {
+            Some('-') => Some(-self.unary()?),
+            Some(_) => self.unary(),
+            None => self.power(),
+        }
+    }
+    fn power(&mut self) -> Option<BigRational> {
+        let base = self.atom()?;
+        if self.next_op(&['^']).is_none() {
+            return Some(base);
+        }
+        let exponent = self.unary()?;
+        // Roots and huge powers are left to floats
+        if !exponent.is_integer() || base.is_zero() && exponent.is_negative() {
+            return None;
+        }
+        let exponent = exponent.to_integer().to_i32()?;
+        let bits = base.numer().bits().max(base.denom().bits());
+        if bits.saturating_mul(exponent.unsigned_abs() as u64) > MAX_POWER_BITS {
+            return None;
+        }
+        Some(base.pow(exponent))
+    }
+    fn atom(&mut self) -> Option<BigRational> {
+        let token = self.tokens.get(self.pos)?.clone();
+        self.pos += 1;
+        match token {
+            Token::Number(n) => Some(n),
+            // Functions are left to floats
+            Token::Ident(_) if self.tokens.get(self.pos) == Some(&Token::Open) => None,
+            Token::Ident(name) => (self.lookup)(&name),
+            Token::Open => {
+                let value = self.expr()?;
+                match self.tokens.get(self.pos) {
+                    Some(Token::Close) => {
+                        self.pos += 1;
+                        Some(value)
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Calculates `expr` exactly. Returns `None` for anything that needs floats,
+/// like functions, roots or names `lookup` doesn't know.
+pub fn evaluate<F>(expr: &str, lookup: &F) -> Option<BigRational>
+where
+    F: Fn(&str) -> Option<BigRational>,
+{
+    let tokens = tokenize(expr)?;
+    let mut parser = Parser {
+        tokens,
+        pos: 0,
+        lookup,
+    };
+    let value = parser.expr()?;
+    (parser.pos == parser.tokens.len()).then_some(value)
+}
+
+#[test]
+fn test_precise() {
+    let lookup = |name: &str| match name {
+        "vat" => from_float(0.19),
+        _ => None,
+    };
+    let en = separators("en_US.UTF-8");
+    let show = |expr: &str| evaluate(expr, &lookup).map(|r| Number::Exact(r).format_with(10, &en));
+
+    assert_eq!(show("0.1 + 0.2").as_deref(), Some("0.3"));
+    assert_eq!(show("19.99 * 3").as_deref(), Some("59.97"));
+    assert_eq!(show("100 * (1 + vat)").as_deref(), Some("119"));
+    assert_eq!(show("2^64").as_deref(), Some("18,446,744,073,709,551,616"));
+    assert_eq!(show("2^-2 - -1").as_deref(), Some("1.25"));
+    assert_eq!(show("-2^2").as_deref(), Some("-4"));
+    assert_eq!(show("1/3").as_deref(), Some("0.3333333333"));
+    assert_eq!(show("2/3").as_deref(), Some("0.6666666667"));
+    assert_eq!(show("7 % 3 + 1.5e3").as_deref(), Some("1,501"));
+    assert_eq!(show("10^25").as_deref(), Some("1e25"));
+    assert_eq!(show("-1.23456e-9").as_deref(), Some("-1.23456e-9"));
+    assert_eq!(show("9.99999999999 * 10^30").as_deref(), Some("1e31"));
+
+    // Left to floats
+    assert_eq!(evaluate("sqrt(2)", &lookup), None);
+    assert_eq!(evaluate("2^0.5", &lookup), None);
+    assert_eq!(evaluate("pi * 2", &lookup), None);
+    assert_eq!(evaluate("1 / 0", &lookup), None);
+    assert_eq!(evaluate("2^2^30", &lookup), None);
+    assert_eq!(evaluate("1e999999999", &lookup), None);
+    assert_eq!(evaluate("1e-999999999", &lookup), None);
+
+    let third = Number::parse("1/3").unwrap();
+    assert_eq!(third.raw(), "1/3");
+    assert_eq!(third.fraction(10).as_deref(), Some("1/3"));
+    assert_eq!(Number::parse("0.25").unwrap().fraction(10), None);
+    assert_eq!(Number::Float(0.1 + 0.2).format_with(10, &en), "0.3");
+    assert_eq!(Number::Float(f64::INFINITY).format_with(10, &en), "inf");
+
+    let de = separators("de_DE.UTF-8");
+    assert_eq!(
+        Number::Float(1234567.891).format_with(2, &de),
+        "1.234.567,89"
+    );
+    assert_eq!(separators("fr_FR").group, "\u{a0}");
+    assert_eq!(separators("de_CH.UTF-8").group, "'");
+    assert_eq!(separators("C"), en);
+}
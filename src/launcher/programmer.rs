@@ -0,0 +1,268 @@
+use regex::Regex;
+use std::sync::LazyLock;
+
+// "255 in hex"
+static CONVERSION_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^(?P<expr>.+?)\s+(?:in|to|as)\s+(?P<base>[a-z]+)\s*$").unwrap()
+});
+
+/// Number bases programmer calculations are shown in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Base {
+    Dec,
+    Hex,
+    Oct,
+    Bin,
+}
+
+impl Base {
+    pub const ALL: [Base; 4] = [Base::Dec, Base::Hex, Base::Oct, Base::Bin];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Base::Dec => "dec",
+            Base::Hex => "hex",
+            Base::Oct => "oct",
+            Base::Bin => "bin",
+        }
+    }
+    fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "dec" | "decimal" => Some(Base::Dec),
+            "hex" | "hexadecimal" => Some(Base::Hex),
+            "oct" | "octal" => Some(Base::Oct),
+            "bin" | "binary" => Some(Base::Bin),
+            _ => None,
+        }
+    }
+    /// The value with its prefix. Negative values are shown in two's
+    /// complement outside of decimal.
+    pub fn format(&self, value: i64) -> String {
+        let bits = value as u64;
+        match self {
+            Base::Dec => value.to_string(),
+            Base::Hex => format!("{:#x}", bits),
+            Base::Oct => format!("{:#o}", bits),
+            Base::Bin => format!("{:#b}", bits),
+        }
+    }
+}
+
+/// The result of a programmer calculation and the base it was asked for
+#[derive(Debug, PartialEq)]
+pub struct ProgrammerResult {
+    pub value: i64,
+    pub base: Base,
+}
+
+impl ProgrammerResult {
+    /// The value in every base, starting with the one asked for
+    pub fn in_bases(&self) -> Vec<(Base, String)> {
+        let mut bases = vec![self.base];
+        bases.extend(Base::ALL.iter().filter(|b| **b != self.base));
+        bases
+            .into_iter()
+            .map(|b| (b, b.format(self.value)))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Token {
+    Number(i64),
+    // Hex, octal or binary literal
+    Literal(i64),
+    Op(&'static str),
+    Open,
+    Close,
+}
+
+// Longer operators first so that "<<" is not read as "<"
+const OPERATORS: [&str; 12] = [
+    "<<", ">>", "//", "+", "-", "*", "/", "%", "&", "|", "^", "~",
+];
+
+fn tokenize(expr: &str) -> Option<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut rest = expr.trim_start();
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('(') {
+            tokens.push(Token::Open);
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix(')') {
+            tokens.push(Token::Close);
+            rest = after;
+        } else if let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(*op)) {
+            tokens.push(Token::Op(op));
+            rest = &rest[op.len()..];
+        } else if rest.starts_with(|c: char| c.is_ascii_digit()) {
+            let end = rest
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                .unwrap_or(rest.len());
+            tokens.push(number(&rest[..end])?);
+            rest = &rest[end..];
+        } else {
+            return None;
+        }
+        rest = rest.trim_start();
+    }
+    Some(tokens)
+}
+
+fn number(literal: &str) -> Option<Token> {
+    let digits = literal.replace('_', "");
+    let lower = digits.to_lowercase();
+    let (radix, digits) = match lower.get(..2) {
+        Some("0x") => (16, &lower[2..]),
+        Some("0o") => (8, &lower[2..]),
+        Some("0b") => (2, &lower[2..]),
+        _ => return digits.parse().ok().map(Token::Number),
+    };
+    // Literals up to 64 bits are read in two's complement
+    let value = u64::from_str_radix(digits, radix).ok()?;
+    Some(Token::Literal(value as i64))
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek_op(&self, ops: &[&str]) -> Option<&'static str> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Op(op)) if ops.contains(op) => Some(op),
+            _ => None,
+        }
+    }
+    /// Parses operators of one precedence level, tightest binding last
+    fn binary(&mut self, level: usize) -> Option<i64> {
+        const LEVELS: [&[&str]; 5] = [&["|"], &["^"], &["&"], &["<<", ">>"], &["+", "-"]];
+        let Some(ops) = LEVELS.get(level) else {
+            return self.term();
+        };
+        let mut value = self.binary(level + 1)?;
+        while let Some(op) = self.peek_op(ops) {
+            self.pos += 1;
+            let rhs = self.binary(level + 1)?;
+            value = apply(op, value, rhs)?;
+        }
+        Some(value)
+    }
+    fn term(&mut self) -> Option<i64> {
+        let mut value = self.unary()?;
+        while let Some(op) = self.peek_op(&["*", "/", "//", "%"]) {
+            self.pos += 1;
+            let rhs = self.unary()?;
+            value = apply(op, value, rhs)?;
+        }
+        Some(value)
+    }
+    fn unary(&mut self) -> Option<i64> {
+        let token = *self.tokens.get(self.pos)?;
+        self.pos += 1;
+        match token {
+            Token::Number(n) | Token::Literal(n) => Some(n),
+            Token::Op("-") => self.unary()?.checked_neg(),
+            Token::Op("+") => self.unary(),
+            Token::Op("~") => Some(!self.unary()?),
+            Token::Open => {
+                let value = self.binary(0)?;
+                match self.tokens.get(self.pos) {
+                    Some(Token::Close) => {
+                        self.pos += 1;
+                        Some(value)
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+fn apply(op: &str, lhs: i64, rhs: i64) -> Option<i64> {
+    match op {
+        "+" => lhs.checked_add(rhs),
+        "-" => lhs.checked_sub(rhs),
+        "*" => lhs.checked_mul(rhs),
+        "/" | "//" => lhs.checked_div(rhs),
+        "%" => lhs.checked_rem(rhs),
+        "&" => Some(lhs & rhs),
+        "|" => Some(lhs | rhs),
+        "^" => Some(lhs ^ rhs),
+        "<<" => lhs.checked_shl(u32::try_from(rhs).ok()?),
+        ">>" => lhs.checked_shr(u32::try_from(rhs).ok()?),
+        _ => None,
+    }
+}
+
+/// Evaluates integer expressions like "0xff + 12", "0b1010 << 3" or
+/// "255 in hex". Plain decimal math without bitwise operators is left to
+/// the regular calculator, so that "2^8" stays a power there.
+pub fn evaluate(keyword: &str) -> Option<ProgrammerResult> {
+    let (expr, base) = match CONVERSION_RE.captures(keyword) {
+        Some(caps) => (
+            caps.name("expr")?.as_str(),
+            Some(Base::parse(&caps["base"])?),
+        ),
+        None => (keyword, None),
+    };
+    let tokens = tokenize(expr)?;
+    let programmer = base.is_some()
+        || tokens.iter().any(|t| {
+            matches!(
+                t,
+                Token::Literal(_) | Token::Op("&" | "|" | "~" | "<<" | ">>" | "//")
+            )
+        });
+    if !programmer {
+        return None;
+    }
+    let mut parser = Parser { tokens, pos: 0 };
+    let value = parser.binary(0)?;
+    if parser.pos != parser.tokens.len() {
+        return None;
+    }
+    Some(ProgrammerResult {
+        value,
+        base: base.unwrap_or(Base::Dec),
+    })
+}
+
+#[test]
+fn test_programmer() {
+    let value = |keyword: &str| evaluate(keyword).map(|r| r.value);
+
+    assert_eq!(value("0xff + 12"), Some(267));
+    assert_eq!(value("0b1010 << 3"), Some(80));
+    assert_eq!(value("0o17 | 0b1_0000"), Some(31));
+    assert_eq!(value("0xf0 & 0x3c ^ 0x01"), Some(0x31));
+    assert_eq!(value("(1 << 4) - 1 & 0xa"), Some(10));
+    assert_eq!(value("7 // 2"), Some(3));
+    assert_eq!(value("0x10 / 3 * 3 + 0x10 % 3"), Some(16));
+    assert_eq!(value("~0 & 0xff"), Some(255));
+
+    let result = evaluate("255 in hex").unwrap();
+    assert_eq!(result.base, Base::Hex);
+    assert_eq!(
+        result.in_bases(),
+        vec![
+            (Base::Hex, String::from("0xff")),
+            (Base::Dec, String::from("255")),
+            (Base::Oct, String::from("0o377")),
+            (Base::Bin, String::from("0b11111111")),
+        ]
+    );
+    assert_eq!(Base::Hex.format(-1), "0xffffffffffffffff");
+    assert_eq!(value("0xffffffffffffffff"), Some(-1));
+
+    // Left to the regular calculator, or not valid at all
+    assert_eq!(evaluate("2^8"), None);
+    assert_eq!(evaluate("1 + 2"), None);
+    assert_eq!(evaluate("255 in km"), None);
+    assert_eq!(evaluate("0xff +"), None);
+    assert_eq!(evaluate("1.5 & 1"), None);
+    assert_eq!(evaluate("1 / 0 in hex"), None);
+    assert_eq!(evaluate("1 << 64"), None);
+}
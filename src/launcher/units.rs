@@ -0,0 +1,316 @@
+use std::f64::consts::PI;
+
+/// What a unit measures. Only units of the same dimension convert into each
+/// other.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dimension {
+    Length,
+    Weight,
+    Volume,
+    Temperature,
+    Duration,
+    Data,
+    DataRate,
+    Speed,
+    Area,
+    Energy,
+    Power,
+    Pressure,
+    Angle,
+    Frequency,
+}
+
+impl Dimension {
+    /// The `calc.*` capability enabling conversions of this dimension
+    pub fn capability(&self) -> &'static str {
+        match self {
+            Dimension::Length => "calc.lengths",
+            Dimension::Weight => "calc.weights",
+            Dimension::Volume => "calc.volumes",
+            Dimension::Temperature => "calc.temperatures",
+            Dimension::Duration => "calc.durations",
+            Dimension::Data => "calc.data",
+            Dimension::DataRate => "calc.datarates",
+            Dimension::Speed => "calc.speeds",
+            Dimension::Area => "calc.areas",
+            Dimension::Energy => "calc.energies",
+            Dimension::Power => "calc.powers",
+            Dimension::Pressure => "calc.pressures",
+            Dimension::Angle => "calc.angles",
+            Dimension::Frequency => "calc.frequencies",
+        }
+    }
+}
+
+/// A unit in terms of the base unit of its dimension:
+/// `base = (value + offset) * factor`
+#[derive(Debug, PartialEq)]
+pub struct Unit {
+    pub symbol: &'static str,
+    pub name: &'static str,
+    pub plural: &'static str,
+    pub aliases: &'static [&'static str],
+    pub dimension: Dimension,
+    pub factor: f64,
+    pub offset: f64,
+}
+
+impl Unit {
+    pub fn to_base(&self, value: f64) -> f64 {
+        (value + self.offset) * self.factor
+    }
+    pub fn to_unit(&self, base: f64) -> f64 {
+        base / self.factor - self.offset
+    }
+    pub fn label(&self, value: f64) -> &'static str {
+        if value == 1.0 {
+            self.name
+        } else {
+            self.plural
+        }
+    }
+    fn is_called(&self, name: &str) -> bool {
+        let name = normalize(name);
+        [self.symbol, self.name, self.plural]
+            .iter()
+            .chain(self.aliases)
+            .any(|n| normalize(n) == name)
+    }
+}
+
+const fn unit(
+    symbol: &'static str,
+    name: &'static str,
+    plural: &'static str,
+    aliases: &'static [&'static str],
+    dimension: Dimension,
+    factor: f64,
+) -> Unit {
+    Unit {
+        symbol,
+        name,
+        plural,
+        aliases,
+        dimension,
+        factor,
+        offset: 0.0,
+    }
+}
+
+use Dimension::*;
+
+// Base units: meter, kilogram, liter, kelvin, second, byte, byte per second,
+// meter per second, square meter, joule, watt, pascal, degree and hertz.
+// Units sharing a name, like "oz", are told apart by the unit they are
+// converted to. Otherwise the first one listed wins.
+#[rustfmt::skip]
+pub static UNITS: &[Unit] = &[
+    unit("km", "Kilometer", "Kilometers", &["kilos"], Length, 1000.0),
+    unit("m", "Meter", "Meters", &[], Length, 1.0),
+    unit("cm", "Centimeter", "Centimeters", &["cents"], Length, 0.01),
+    unit("mm", "Millimeter", "Millimeters", &[], Length, 0.001),
+    unit("µm", "Micrometer", "Micrometers", &["um"], Length, 1e-6),
+    unit("nm", "Nanometer", "Nanometers", &[], Length, 1e-9),
+    unit("in", "Inch", "Inches", &[], Length, 0.0254),
+    unit("ft", "Foot", "Feet", &[], Length, 0.3048),
+    unit("yd", "Yard", "Yards", &[], Length, 0.9144),
+    unit("mi", "Mile", "Miles", &[], Length, 1609.344),
+    unit("nmi", "Nautical Mile", "Nautical Miles", &[], Length, 1852.0),
+    unit("t", "Tonne", "Tonnes", &["metric ton"], Weight, 1000.0),
+    unit("kg", "Kilogram", "Kilograms", &[], Weight, 1.0),
+    unit("g", "Gram", "Grams", &[], Weight, 0.001),
+    unit("mg", "Milligram", "Milligrams", &[], Weight, 1e-6),
+    unit("µg", "Microgram", "Micrograms", &["ug", "mcg"], Weight, 1e-9),
+    unit("lb", "Pound", "Pounds", &["lbs"], Weight, 0.45359237),
+    unit("oz", "Ounce", "Ounces", &[], Weight, 0.028349523125),
+    unit("st", "Stone", "Stones", &[], Weight, 6.35029318),
+    unit("ct", "Carat", "Carats", &[], Weight, 0.0002),
+    unit("m³", "Cubic Meter", "Cubic Meters", &["m3"], Volume, 1000.0),
+    unit("l", "Liter", "Liters", &["litre", "litres"], Volume, 1.0),
+    unit("dl", "Deciliter", "Deciliters", &[], Volume, 0.1),
+    unit("cl", "Centiliter", "Centiliters", &[], Volume, 0.01),
+    unit("ml", "Milliliter", "Milliliters", &[], Volume, 0.001),
+    unit("gal", "Gallon", "Gallons", &[], Volume, 3.785411784),
+    unit("qt", "Quart", "Quarts", &[], Volume, 0.946352946),
+    unit("pt", "Pint", "Pints", &[], Volume, 0.473176473),
+    unit("cup", "Cup", "Cups", &[], Volume, 0.24),
+    unit("fl oz", "Fluid Ounce", "Fluid Ounces", &["oz"], Volume, 0.0295735295625),
+    unit("tbsp", "Tablespoon", "Tablespoons", &[], Volume, 0.01478676478125),
+    unit("tsp", "Teaspoon", "Teaspoons", &[], Volume, 0.00492892159375),
+    unit("pinch", "Pinch", "Pinches", &[], Volume, 0.00492892159375 / 16.0),
+    unit("dash", "Dash", "Dashes", &[], Volume, 0.00492892159375 / 8.0),
+    Unit { offset: 273.15, ..unit("°C", "Degree Celsius", "Degrees Celsius", &["c", "celsius"], Temperature, 1.0) },
+    Unit { offset: 459.67, ..unit("°F", "Degree Fahrenheit", "Degrees Fahrenheit", &["f", "fahrenheit"], Temperature, 5.0 / 9.0) },
+    unit("K", "Kelvin", "Kelvin", &["k", "kelvin"], Temperature, 1.0),
+    unit("ns", "Nanosecond", "Nanoseconds", &[], Duration, 1e-9),
+    unit("µs", "Microsecond", "Microseconds", &["us"], Duration, 1e-6),
+    unit("ms", "Millisecond", "Milliseconds", &[], Duration, 0.001),
+    unit("s", "Second", "Seconds", &["sec", "secs"], Duration, 1.0),
+    unit("min", "Minute", "Minutes", &["mins"], Duration, 60.0),
+    unit("h", "Hour", "Hours", &["hr", "hrs"], Duration, 3600.0),
+    unit("d", "Day", "Days", &[], Duration, 86400.0),
+    unit("wk", "Week", "Weeks", &[], Duration, 604800.0),
+    unit("mo", "Month", "Months", &[], Duration, 2629746.0),
+    unit("yr", "Year", "Years", &["y"], Duration, 31556952.0),
+    unit("B", "Byte", "Bytes", &[], Data, 1.0),
+    unit("kB", "Kilobyte", "Kilobytes", &[], Data, 1e3),
+    unit("MB", "Megabyte", "Megabytes", &[], Data, 1e6),
+    unit("GB", "Gigabyte", "Gigabytes", &[], Data, 1e9),
+    unit("TB", "Terabyte", "Terabytes", &[], Data, 1e12),
+    unit("PB", "Petabyte", "Petabytes", &[], Data, 1e15),
+    unit("KiB", "Kibibyte", "Kibibytes", &[], Data, 1024.0),
+    unit("MiB", "Mebibyte", "Mebibytes", &[], Data, 1048576.0),
+    unit("GiB", "Gibibyte", "Gibibytes", &[], Data, 1073741824.0),
+    unit("TiB", "Tebibyte", "Tebibytes", &[], Data, 1099511627776.0),
+    unit("b", "Bit", "Bits", &["bit"], Data, 0.125),
+    unit("kb", "Kilobit", "Kilobits", &["kbit"], Data, 125.0),
+    unit("Mb", "Megabit", "Megabits", &["Mbit"], Data, 125e3),
+    unit("Gb", "Gigabit", "Gigabits", &["Gbit"], Data, 125e6),
+    unit("Tb", "Terabit", "Terabits", &["Tbit"], Data, 125e9),
+    unit("B/s", "Byte per Second", "Bytes per Second", &[], DataRate, 1.0),
+    unit("kB/s", "Kilobyte per Second", "Kilobytes per Second", &[], DataRate, 1e3),
+    unit("MB/s", "Megabyte per Second", "Megabytes per Second", &[], DataRate, 1e6),
+    unit("GB/s", "Gigabyte per Second", "Gigabytes per Second", &[], DataRate, 1e9),
+    unit("KiB/s", "Kibibyte per Second", "Kibibytes per Second", &[], DataRate, 1024.0),
+    unit("MiB/s", "Mebibyte per Second", "Mebibytes per Second", &[], DataRate, 1048576.0),
+    unit("GiB/s", "Gibibyte per Second", "Gibibytes per Second", &[], DataRate, 1073741824.0),
+    unit("bit/s", "Bit per Second", "Bits per Second", &["bps"], DataRate, 0.125),
+    unit("kbit/s", "Kilobit per Second", "Kilobits per Second", &["kbps"], DataRate, 125.0),
+    unit("Mbit/s", "Megabit per Second", "Megabits per Second", &["Mbps"], DataRate, 125e3),
+    unit("Gbit/s", "Gigabit per Second", "Gigabits per Second", &["Gbps"], DataRate, 125e6),
+    unit("m/s", "Meter per Second", "Meters per Second", &["mps"], Speed, 1.0),
+    unit("km/h", "Kilometer per Hour", "Kilometers per Hour", &["kmh", "kph"], Speed, 1.0 / 3.6),
+    unit("mph", "Mile per Hour", "Miles per Hour", &[], Speed, 0.44704),
+    unit("kn", "Knot", "Knots", &["kt"], Speed, 1852.0 / 3600.0),
+    unit("ft/s", "Foot per Second", "Feet per Second", &["fps"], Speed, 0.3048),
+    unit("mm²", "Square Millimeter", "Square Millimeters", &["mm2"], Area, 1e-6),
+    unit("cm²", "Square Centimeter", "Square Centimeters", &["cm2"], Area, 1e-4),
+    unit("m²", "Square Meter", "Square Meters", &["m2", "sqm"], Area, 1.0),
+    unit("ha", "Hectare", "Hectares", &[], Area, 1e4),
+    unit("km²", "Square Kilometer", "Square Kilometers", &["km2"], Area, 1e6),
+    unit("in²", "Square Inch", "Square Inches", &["in2", "sqin"], Area, 0.00064516),
+    unit("ft²", "Square Foot", "Square Feet", &["ft2", "sqft"], Area, 0.09290304),
+    unit("yd²", "Square Yard", "Square Yards", &["yd2"], Area, 0.83612736),
+    unit("ac", "Acre", "Acres", &[], Area, 4046.8564224),
+    unit("mi²", "Square Mile", "Square Miles", &["mi2", "sqmi"], Area, 2589988.110336),
+    unit("J", "Joule", "Joules", &[], Energy, 1.0),
+    unit("kJ", "Kilojoule", "Kilojoules", &[], Energy, 1e3),
+    unit("MJ", "Megajoule", "Megajoules", &[], Energy, 1e6),
+    unit("cal", "Calorie", "Calories", &[], Energy, 4.184),
+    unit("kcal", "Kilocalorie", "Kilocalories", &[], Energy, 4184.0),
+    unit("Wh", "Watt Hour", "Watt Hours", &[], Energy, 3600.0),
+    unit("kWh", "Kilowatt Hour", "Kilowatt Hours", &[], Energy, 3.6e6),
+    unit("eV", "Electronvolt", "Electronvolts", &[], Energy, 1.602176634e-19),
+    unit("BTU", "British Thermal Unit", "British Thermal Units", &[], Energy, 1055.05585262),
+    unit("mW", "Milliwatt", "Milliwatts", &[], Power, 0.001),
+    unit("W", "Watt", "Watts", &[], Power, 1.0),
+    unit("kW", "Kilowatt", "Kilowatts", &[], Power, 1e3),
+    unit("MW", "Megawatt", "Megawatts", &[], Power, 1e6),
+    unit("hp", "Horsepower", "Horsepower", &[], Power, 745.6998715822702),
+    unit("PS", "Metric Horsepower", "Metric Horsepower", &[], Power, 735.49875),
+    unit("Pa", "Pascal", "Pascals", &[], Pressure, 1.0),
+    unit("hPa", "Hectopascal", "Hectopascals", &[], Pressure, 100.0),
+    unit("kPa", "Kilopascal", "Kilopascals", &[], Pressure, 1e3),
+    unit("MPa", "Megapascal", "Megapascals", &[], Pressure, 1e6),
+    unit("bar", "Bar", "Bars", &[], Pressure, 1e5),
+    unit("mbar", "Millibar", "Millibars", &[], Pressure, 100.0),
+    unit("atm", "Atmosphere", "Atmospheres", &[], Pressure, 101325.0),
+    unit("psi", "Pound per Square Inch", "Pounds per Square Inch", &[], Pressure, 6894.757293168),
+    unit("mmHg", "Millimeter of Mercury", "Millimeters of Mercury", &[], Pressure, 133.322387415),
+    unit("Torr", "Torr", "Torr", &[], Pressure, 101325.0 / 760.0),
+    unit("°", "Degree", "Degrees", &["deg"], Angle, 1.0),
+    unit("rad", "Radian", "Radians", &[], Angle, 180.0 / PI),
+    unit("grad", "Gradian", "Gradians", &["gon"], Angle, 0.9),
+    unit("arcmin", "Arcminute", "Arcminutes", &[], Angle, 1.0 / 60.0),
+    unit("arcsec", "Arcsecond", "Arcseconds", &[], Angle, 1.0 / 3600.0),
+    unit("turn", "Turn", "Turns", &["rev"], Angle, 360.0),
+    unit("Hz", "Hertz", "Hertz", &[], Frequency, 1.0),
+    unit("kHz", "Kilohertz", "Kilohertz", &[], Frequency, 1e3),
+    unit("MHz", "Megahertz", "Megahertz", &[], Frequency, 1e6),
+    unit("GHz", "Gigahertz", "Gigahertz", &[], Frequency, 1e9),
+    unit("rpm", "Revolution per Minute", "Revolutions per Minute", &[], Frequency, 1.0 / 60.0),
+];
+
+/// Lowercase and without whitespace, so "Fluid Ounces" matches "fluidounces"
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Units called `name`. Exact symbols come first, so that "MB" is a megabyte
+/// and "Mb" a megabit.
+pub fn find(name: &str) -> Vec<&'static Unit> {
+    let name = name.trim();
+    let exact = UNITS.iter().filter(|u| u.symbol == name);
+    let others = UNITS
+        .iter()
+        .filter(|u| u.symbol != name && u.is_called(name));
+    exact.chain(others).collect()
+}
+
+/// The units called `from` and `to` that share a dimension enabled by
+/// `enabled`
+pub fn resolve(
+    from: &str,
+    to: &str,
+    enabled: impl Fn(Dimension) -> bool,
+) -> Option<(&'static Unit, &'static Unit)> {
+    let targets = find(to);
+    find(from)
+        .into_iter()
+        .filter(|from| enabled(from.dimension))
+        .find_map(|from| {
+            let to = targets.iter().find(|to| to.dimension == from.dimension)?;
+            Some((from, *to))
+        })
+}
+
+pub fn convert(value: f64, from: &Unit, to: &Unit) -> f64 {
+    to.to_unit(from.to_base(value))
+}
+
+#[test]
+fn test_units() {
+    let all = |_| true;
+    let approx = |a: f64, b: f64| (a - b).abs() < 1e-9 * b.abs().max(1.0);
+    let conversion = |value: f64, from: &str, to: &str| {
+        let (from, to) = resolve(from, to, all).unwrap();
+        convert(value, from, to)
+    };
+
+    assert!(approx(conversion(5.0, "km", "m"), 5000.0));
+    assert!(approx(conversion(90.0, "min", "h"), 1.5));
+    assert!(approx(conversion(7_200_000.0, "ms", "hours"), 2.0));
+
+    // Decimal and binary prefixes, bytes and bits
+    assert!(approx(conversion(1.0, "GiB", "MB"), 1073.741824));
+    assert!(approx(conversion(100.0, "Mbps", "MB/s"), 12.5));
+    assert_eq!(find("MB")[0].name, "Megabyte");
+    assert_eq!(find("Mb")[0].name, "Megabit");
+
+    assert!(approx(conversion(100.0, "km/h", "m/s"), 27.77777777777778));
+    assert!(approx(conversion(1.0, "ha", "sqm"), 10000.0));
+    assert!(approx(conversion(1.0, "kWh", "kJ"), 3600.0));
+    assert!(approx(conversion(1.0, "atm", "hPa"), 1013.25));
+    assert!(approx(conversion(180.0, "deg", "rad"), PI));
+    assert!(approx(conversion(3000.0, "rpm", "Hz"), 50.0));
+
+    // Temperatures are offset from kelvin
+    assert!(approx(conversion(100.0, "c", "f"), 212.0));
+    assert!(approx(conversion(0.0, "kelvin", "celsius"), -273.15));
+    assert!(approx(conversion(32.0, "°F", "K"), 273.15));
+
+    // Ounces are told apart by the unit they are converted to
+    let (oz, _) = resolve("oz", "ml", all).unwrap();
+    assert_eq!(oz.dimension, Volume);
+    let (oz, _) = resolve("oz", "g", all).unwrap();
+    assert_eq!(oz.dimension, Weight);
+
+    // Spoons and pinches are volumes, so they don't convert to weights
+    assert!(approx(conversion(1.0, "tbsp", "tsp"), 3.0));
+    assert!(resolve("tbsp", "g", all).is_none());
+    assert!(resolve("kg", "m", all).is_none());
+    assert!(resolve("km", "m", |d| d == Weight).is_none());
+}
@@ -1,5 +1,6 @@
 #[derive(Clone, Debug)]
 pub struct Web {
+    pub priority: f32,
     pub display_name: String,
     pub icon: String,
     pub engine: String,
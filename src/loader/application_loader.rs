@@ -1,6 +1,6 @@
 use glob::Pattern;
 use rayon::prelude::*;
-use regex::Regex;
+use serde::{Deserialize, Serialize};
 use simd_json;
 use std::collections::{HashMap, HashSet};
 use std::env;
@@ -8,17 +8,37 @@ use std::fs::{self, File};
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
+use super::desktop_entry::{current_desktops, current_locales, DesktopFile};
+use super::frecency::frecency_priority;
 use super::util::{SherlockError, SherlockErrorType};
 use super::{util, Loader};
 use crate::CONFIG;
-use util::{parse_priority, read_file, read_lines, AppData, SherlockAlias};
+use util::{read_file, read_lines, AppAction, AppData, SherlockAlias};
+
+// Bump whenever AppData gains fields parsed from the desktop files
+const CACHE_VERSION: u32 = 5;
+
+#[derive(Serialize, Deserialize)]
+struct AppCache {
+    version: u32,
+    /// Locale the names were translated for
+    locale: String,
+    apps: HashMap<String, AppData>,
+}
+
+fn cache_locale() -> String {
+    current_locales()
+        .iter()
+        .map(|l| l.variants().remove(0))
+        .collect::<Vec<_>>()
+        .join(":")
+}
 
 impl Loader {
     pub fn load_applications_from_disk(
         applications: Option<HashSet<PathBuf>>,
         priority: f32,
-        counts: HashMap<String, f32>,
-        decimals: i32,
+        scores: HashMap<String, f32>,
     ) -> Result<HashMap<String, AppData>, SherlockError> {
         let config = CONFIG.get().ok_or(SherlockError {
             error: SherlockErrorType::ConfigError(None),
@@ -28,16 +48,9 @@ impl Loader {
         // Define required paths for application parsing
         let system_apps = get_applications_dir();
 
-        // Parse needed fields from the '.desktop'
-        let (name_re, icon_re, exec_re, display_re, terminal_re, keywords_re) =
-            get_regex_patterns().map_err(|e| return e)?;
-
-        let parse_field = |content: &str, regex: &Regex| {
-            regex
-                .captures(content)
-                .and_then(|caps| caps.get(1).map(|m| m.as_str().to_string()))
-                .unwrap_or_default()
-        };
+        // Entries can be limited to certain desktop environments
+        let desktops = current_desktops();
+        let locales = current_locales();
 
         // Parse user-specified 'sherlockignore' file
         let ignore_apps: Vec<Pattern> = match read_lines(&config.files.ignore) {
@@ -78,36 +91,78 @@ impl Loader {
                 let r_path = entry.to_str()?;
                 match read_file(r_path) {
                     Ok(content) => {
-                        if parse_field(&content, &display_re) == "true" {
+                        let file = DesktopFile::parse(&content);
+                        let desktop_entry = file.entry()?;
+                        if !desktop_entry.is_shown(&desktops) {
                             return None;
                         }
 
-                        // Extract keywords, icon, and name fields
-                        let mut keywords = parse_field(&content, &keywords_re);
-                        let mut icon = parse_field(&content, &icon_re);
-                        let mut name = parse_field(&content, &name_re);
+                        // Extract keywords, icon, and name fields. The untranslated
+                        // name and keywords stay searchable next to the localized ones.
+                        let mut icon = desktop_entry.string("Icon").unwrap_or_default();
+                        let default_name = desktop_entry.string("Name").unwrap_or_default();
+                        let mut name = desktop_entry
+                            .localized_string("Name", &locales)
+                            .unwrap_or_default();
                         if name.is_empty() || should_ignore(&ignore_apps, &name) {
                             return None; // Skip entries with empty names
                         }
+                        let mut keywords = desktop_entry.localized_list("Keywords", &locales);
+                        keywords.push(default_name.clone());
+                        keywords.extend(desktop_entry.list("Keywords"));
+                        let mut seen = HashSet::new();
+                        keywords.retain(|k| k != &name && seen.insert(k.clone()));
+                        let mut keywords = keywords.join(";");
+                        let generic_name = desktop_entry.localized_string("GenericName", &locales);
+                        let comment = desktop_entry.localized_string("Comment", &locales);
+                        let working_dir = desktop_entry
+                            .string("Path")
+                            .filter(|p| !p.is_empty())
+                            .map(PathBuf::from);
 
                         // Construct the executable command
-                        let mut exec = config
-                            .behavior
-                            .global_prefix
-                            .as_ref()
-                            .map_or(String::new(), |pre| format!("{} ", pre));
-                        if parse_field(&content, &terminal_re) == "true" {
-                            exec.push_str(&config.default_apps.terminal);
-                            exec.push(' ');
-                        }
-                        exec.push_str(&parse_field(&content, &exec_re));
-                        if let Some(flag) = &config.behavior.global_flags {
-                            exec.push(' ');
-                            exec.push_str(&flag);
-                        }
-
-                        // apply aliases
-                        if let Some(alias) = aliases.get(&name) {
+                        let terminal = desktop_entry.boolean("Terminal");
+                        let build_exec = |raw: &str| {
+                            let mut exec = config
+                                .behavior
+                                .global_prefix
+                                .as_ref()
+                                .map_or(String::new(), |pre| format!("{} ", pre));
+                            if terminal {
+                                exec.push_str(&config.default_apps.terminal);
+                                exec.push(' ');
+                            }
+                            exec.push_str(raw);
+                            if let Some(flag) = &config.behavior.global_flags {
+                                exec.push(' ');
+                                exec.push_str(&flag);
+                            }
+                            exec
+                        };
+                        let mut exec =
+                            build_exec(&desktop_entry.string("Exec").unwrap_or_default());
+
+                        // Actions without an exec are only reachable through D-Bus
+                        let actions: Vec<AppAction> = desktop_entry
+                            .list("Actions")
+                            .iter()
+                            .filter_map(|id| {
+                                let group = file.group(&format!("Desktop Action {}", id))?;
+                                let name = group.localized_string("Name", &locales)?;
+                                let exec = group.string("Exec").filter(|e| !e.trim().is_empty())?;
+                                Some(AppAction {
+                                    id: id.clone(),
+                                    name,
+                                    exec: build_exec(&exec),
+                                    icon: group.string("Icon").filter(|i| !i.is_empty()),
+                                })
+                            })
+                            .collect();
+
+                        // apply aliases. A custom exec replaces D-Bus activation.
+                        let mut dbus_activatable = desktop_entry.boolean("DBusActivatable");
+                        let alias = aliases.get(&name).or_else(|| aliases.get(&default_name));
+                        if let Some(alias) = alias {
                             if let Some(alias_name) = alias.name.as_ref() {
                                 name = alias_name.to_string();
                             }
@@ -119,18 +174,14 @@ impl Loader {
                             }
                             if let Some(alias_exec) = alias.exec.as_ref() {
                                 exec = alias_exec.to_string();
+                                dbus_activatable = false;
                             }
                         };
                         let search_string = format!("{};{}", name, keywords);
 
-                        let desktop_file_path = match config.behavior.caching {
-                            true => Some(entry),
-                            false => None,
-                        };
-
-                        // apply counts
-                        let count = counts.get(&exec).unwrap_or(&0.0);
-                        let priority = parse_priority(priority, *count, decimals);
+                        // apply frecency
+                        let score = scores.get(&exec).copied().unwrap_or(0.0);
+                        let priority = frecency_priority(priority, score);
 
                         // Return the processed app data
                         Some((
@@ -140,9 +191,15 @@ impl Loader {
                                 icon_class: None,
                                 exec,
                                 search_string,
+                                generic_name,
+                                comment,
                                 tag_start: None,
                                 tag_end: None,
-                                desktop_file: desktop_file_path,
+                                desktop_file: Some(entry),
+                                working_dir,
+                                actions,
+                                startup_notify: desktop_entry.boolean("StartupNotify"),
+                                dbus_activatable,
                                 priority,
                             },
                         ))
@@ -157,8 +214,7 @@ impl Loader {
     fn get_new_applications(
         mut apps: HashMap<String, AppData>,
         priority: f32,
-        counts: HashMap<String, f32>,
-        decimals: i32,
+        scores: HashMap<String, f32>,
     ) -> Result<HashMap<String, AppData>, SherlockError> {
         let system_apps = get_applications_dir();
 
@@ -183,7 +239,7 @@ impl Loader {
         });
 
         // get information for uncached applications
-        match Loader::load_applications_from_disk(Some(desktop_files), priority, counts, decimals) {
+        match Loader::load_applications_from_disk(Some(desktop_files), priority, scores) {
             Ok(new_apps) => apps.extend(new_apps),
             _ => {}
         };
@@ -197,8 +253,13 @@ impl Loader {
         }
         let tmp_path = path.with_extension(".tmp");
 
+        let cache = AppCache {
+            version: CACHE_VERSION,
+            locale: cache_locale(),
+            apps: apps.clone(),
+        };
         if let Ok(f) = File::create(&tmp_path) {
-            if let Ok(_) = simd_json::to_writer(f, &apps) {
+            if let Ok(_) = simd_json::to_writer(f, &cache) {
                 let _ = fs::rename(&tmp_path, &cache_loc);
             } else {
                 let _ = fs::remove_file(&tmp_path);
@@ -208,8 +269,7 @@ impl Loader {
 
     pub fn load_applications(
         priority: f32,
-        counts: HashMap<String, f32>,
-        decimals: i32,
+        scores: HashMap<String, f32>,
     ) -> Result<HashMap<String, AppData>, SherlockError> {
         let config = CONFIG.get().ok_or_else(|| SherlockError {
             error: SherlockErrorType::ConfigError(None),
@@ -225,24 +285,25 @@ impl Loader {
             || file_has_changed(&config_path, &cache_path);
 
         if !changed {
+            // Caches of older versions lack fields and are rebuilt, as are
+            // caches translated for another locale
             let cached_apps: Option<HashMap<String, AppData>> = File::open(&config.behavior.cache)
                 .ok()
-                .and_then(|f| simd_json::from_reader(f).ok());
+                .and_then(|f| simd_json::from_reader::<_, AppCache>(f).ok())
+                .filter(|cache| cache.version == CACHE_VERSION && cache.locale == cache_locale())
+                .map(|cache| cache.apps);
 
             if let Some(mut apps) = cached_apps {
-                // apply the current counts
+                // apply the current frecency
                 for (_, v) in apps.iter_mut() {
-                    let count = counts.get(&v.exec).unwrap_or(&0.0);
-                    let priority = parse_priority(priority, *count, decimals);
-                    v.priority = priority
+                    let score = scores.get(&v.exec).copied().unwrap_or(0.0);
+                    v.priority = frecency_priority(priority, score);
                 }
 
                 // Refresh cache in the background
                 let old_apps = apps.clone();
                 rayon::spawn_fifo(move || {
-                    if let Ok(new_apps) =
-                        Loader::get_new_applications(old_apps, priority, counts, decimals)
-                    {
+                    if let Ok(new_apps) = Loader::get_new_applications(old_apps, priority, scores) {
                         Loader::write_cache(&new_apps, &config.behavior.cache);
                     }
                 });
@@ -250,7 +311,7 @@ impl Loader {
             }
         }
 
-        let apps = Loader::load_applications_from_disk(None, priority, counts, decimals)?;
+        let apps = Loader::load_applications_from_disk(None, priority, scores)?;
         // Write the cache in the background
         let app_clone = apps.clone();
         rayon::spawn_fifo(move || Loader::write_cache(&app_clone, &config.behavior.cache));
@@ -263,23 +324,6 @@ fn should_ignore(ignore_apps: &Vec<Pattern>, app: &str) -> bool {
     ignore_apps.iter().any(|pattern| pattern.matches(&app_name))
 }
 
-fn get_regex_patterns() -> Result<(Regex, Regex, Regex, Regex, Regex, Regex), SherlockError> {
-    fn construct_pattern(key: &str) -> Result<Regex, SherlockError> {
-        let pattern = format!(r"(?i)\n{}\s*=\s*(.*)\n", key);
-        Regex::new(&pattern).map_err(|e| SherlockError {
-            error: SherlockErrorType::RegexError(key.to_string()),
-            traceback: e.to_string(),
-        })
-    }
-    let name = construct_pattern("Name")?;
-    let icon = construct_pattern("Icon")?;
-    let exec = construct_pattern("Exec")?;
-    let display = construct_pattern("NoDisplay")?;
-    let terminal = construct_pattern("Terminal")?;
-    let keywords = construct_pattern("Keywords")?;
-    return Ok((name, icon, exec, display, terminal, keywords));
-}
-
 pub fn get_applications_dir() -> HashSet<PathBuf> {
     let xdg_paths = match env::var("XDG_DATA_DIRS").ok() {
         Some(paths) => {
@@ -0,0 +1,36 @@
+use super::database::SherlockDatabase;
+use super::frecency::unix_now;
+use super::util::{default_calc_history, SherlockError};
+use crate::CONFIG;
+
+/// An evaluated expression and its result as shown in the calculator
+#[derive(Clone, Debug, PartialEq)]
+pub struct Calculation {
+    pub expression: String,
+    pub result: String,
+}
+
+/// Number of calculations kept in the history
+pub fn history_size() -> usize {
+    CONFIG
+        .get()
+        .map_or_else(default_calc_history, |c| c.calc.history)
+}
+
+/// Adds `expression` to the calculator history
+pub fn record_calculation(expression: &str, result: &str) -> Result<(), SherlockError> {
+    let expression = expression.trim();
+    if expression.is_empty() {
+        return Ok(());
+    }
+    let calculation = Calculation {
+        expression: expression.to_string(),
+        result: result.to_string(),
+    };
+    SherlockDatabase::with(|db| db.record_calculation(&calculation, unix_now(), history_size()))
+}
+
+/// The calculator history, newest first
+pub fn recent_calculations() -> Vec<Calculation> {
+    SherlockDatabase::with(|db| db.calculations(history_size())).unwrap_or_default()
+}
@@ -145,6 +145,17 @@ impl Loader {
                 .unwrap_or(&config.files.ignore),
             &home,
         );
+        config.files.socket = expand_path(
+            &sherlock_flags
+                .socket
+                .as_deref()
+                .unwrap_or(&config.files.socket),
+            &home,
+        );
+        config.files.lock = expand_path(
+            &sherlock_flags.lock.as_deref().unwrap_or(&config.files.lock),
+            &home,
+        );
         config.behavior.cache = expand_path(
             &sherlock_flags
                 .cache
@@ -1,11 +1,18 @@
 use gtk4::gdk::Display;
 use gtk4::CssProvider;
+use std::cell::RefCell;
 use std::path::Path;
+use std::rc::Rc;
 
 use super::util::{SherlockError, SherlockErrorType};
 use super::Loader;
 use crate::CONFIG;
 
+thread_local! {
+    // Providers that are currently attached to the display
+    static PROVIDERS: RefCell<Vec<CssProvider>> = RefCell::new(Vec::new());
+}
+
 impl Loader {
     pub fn load_css() -> Result<Vec<SherlockError>, SherlockError> {
         let mut non_breaking: Vec<SherlockError> = Vec::new();
@@ -20,6 +27,13 @@ impl Loader {
             traceback: "No display available".to_string(),
         })?;
 
+        // Remove previously loaded styles to allow reloading
+        PROVIDERS.with_borrow_mut(|providers| {
+            providers
+                .drain(..)
+                .for_each(|p| gtk4::style_context_remove_provider_for_display(&display, &p));
+        });
+
         // Load the base line css
         if config.appearance.use_base_css {
             provider.load_from_resource("/dev/skxxtz/sherlock/main.css");
@@ -28,17 +42,35 @@ impl Loader {
                 &provider,
                 gtk4::STYLE_PROVIDER_PRIORITY_APPLICATION,
             );
+            PROVIDERS.with_borrow_mut(|providers| providers.push(provider));
         }
 
         // Load the user css
         if Path::new(&config.files.css).exists() {
             let usr_provider = CssProvider::new();
+            let parse_errors: Rc<RefCell<Vec<String>>> = Rc::new(RefCell::new(Vec::new()));
+            let errors_clone = Rc::clone(&parse_errors);
+            usr_provider.connect_parsing_error(move |_, section, error| {
+                let location = section.start_location();
+                errors_clone.borrow_mut().push(format!(
+                    "Line {}: {}",
+                    location.lines() + 1,
+                    error.message()
+                ));
+            });
             usr_provider.load_from_path(&config.files.css);
+            if !parse_errors.borrow().is_empty() {
+                non_breaking.push(SherlockError {
+                    error: SherlockErrorType::FileParseError(config.files.css.clone()),
+                    traceback: parse_errors.borrow().join("\n"),
+                });
+            }
             gtk4::style_context_add_provider_for_display(
                 &display,
                 &usr_provider,
                 gtk4::STYLE_PROVIDER_PRIORITY_USER,
             );
+            PROVIDERS.with_borrow_mut(|providers| providers.push(usr_provider));
         } else {
             non_breaking.push(SherlockError {
                 error: SherlockErrorType::FileExistError(config.files.css.clone()),
@@ -46,7 +78,6 @@ impl Loader {
             });
         }
 
-        drop(provider);
         Ok(non_breaking)
     }
 }
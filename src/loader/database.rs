@@ -0,0 +1,436 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, UNIX_EPOCH};
+
+use super::calc_history::Calculation;
+use super::frecency::{migrate_counts, unix_now, LaunchHistory, MAX_LAUNCHES};
+use super::query_memory::{forgotten, Selection};
+use super::util::{SherlockError, SherlockErrorType};
+
+// Other Sherlock instances may hold the write lock for a moment
+const BUSY_TIMEOUT: Duration = Duration::from_secs(2);
+
+// Each entry upgrades the schema by one version. Never edit released entries,
+// append new ones instead.
+const MIGRATIONS: &[&str] = &[
+    // 1: launches, query history and per-launcher state
+    "CREATE TABLE launches (
+        id INTEGER PRIMARY KEY,
+        exec TEXT NOT NULL,
+        time INTEGER NOT NULL
+    );
+    CREATE INDEX launches_exec ON launches (exec, time);
+    CREATE TABLE query_history (
+        query TEXT NOT NULL,
+        exec TEXT NOT NULL,
+        count INTEGER NOT NULL,
+        last INTEGER NOT NULL,
+        PRIMARY KEY (query, exec)
+    );
+    CREATE TABLE launcher_state (
+        launcher TEXT NOT NULL,
+        key TEXT NOT NULL,
+        value TEXT NOT NULL,
+        PRIMARY KEY (launcher, key)
+    );",
+    // 2: calculator history
+    "CREATE TABLE calc_history (
+        id INTEGER PRIMARY KEY,
+        expression TEXT NOT NULL,
+        result TEXT NOT NULL,
+        time INTEGER NOT NULL
+    );",
+];
+
+// Opened and migrated on first use, then shared by every caller
+static DATABASE: Mutex<Option<SherlockDatabase>> = Mutex::new(None);
+
+/// Usage data of Sherlock, stored in `~/.local/share/sherlock/sherlock.db`
+pub struct SherlockDatabase {
+    conn: Connection,
+    path: PathBuf,
+}
+
+impl SherlockDatabase {
+    /// Runs `f` on the shared connection. Opening is retried on the next call
+    /// if it fails.
+    pub fn with<T>(
+        f: impl FnOnce(&mut SherlockDatabase) -> Result<T, SherlockError>,
+    ) -> Result<T, SherlockError> {
+        let mut database = DATABASE.lock().unwrap_or_else(|e| e.into_inner());
+        let db = match database.as_mut() {
+            Some(db) => db,
+            None => database.insert(Self::open()?),
+        };
+        f(db)
+    }
+    fn open() -> Result<Self, SherlockError> {
+        let path = database_path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| SherlockError {
+                error: SherlockErrorType::DirCreateError(parent.to_string_lossy().to_string()),
+                traceback: e.to_string(),
+            })?;
+        }
+        let conn = Connection::open(&path).map_err(|e| db_error(&path, e))?;
+        let mut db = Self { conn, path };
+        db.setup()?;
+        db.import_legacy_files()?;
+        Ok(db)
+    }
+    fn setup(&mut self) -> Result<(), SherlockError> {
+        self.conn
+            .busy_timeout(BUSY_TIMEOUT)
+            .map_err(|e| db_error(&self.path, e))?;
+        // WAL lets readers continue while another instance writes
+        self.conn
+            .pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))
+            .map_err(|e| db_error(&self.path, e))?;
+        self.migrate()
+    }
+    /// Brings the schema up to date. The current version is kept in
+    /// `PRAGMA user_version`.
+    fn migrate(&mut self) -> Result<(), SherlockError> {
+        let path = self.path.clone();
+        let tx = self.conn.transaction().map_err(|e| db_error(&path, e))?;
+        let version: usize = tx
+            .pragma_query_value(None, "user_version", |row| row.get(0))
+            .map_err(|e| db_error(&path, e))?;
+        for (i, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            tx.execute_batch(migration)
+                .map_err(|e| db_error(&path, e))?;
+            tx.pragma_update(None, "user_version", i + 1)
+                .map_err(|e| db_error(&path, e))?;
+        }
+        tx.commit().map_err(|e| db_error(&path, e))
+    }
+
+    /// Records a launch of `exec` and drops its launches beyond the limit
+    pub fn record_launch(&mut self, exec: &str, time: u64) -> Result<(), SherlockError> {
+        let path = self.path.clone();
+        let tx = self.conn.transaction().map_err(|e| db_error(&path, e))?;
+        tx.execute(
+            "INSERT INTO launches (exec, time) VALUES (?1, ?2)",
+            params![exec, time as i64],
+        )
+        .map_err(|e| db_error(&path, e))?;
+        tx.execute(
+            "DELETE FROM launches WHERE exec = ?1 AND id NOT IN (
+                SELECT id FROM launches WHERE exec = ?1 ORDER BY time DESC, id DESC LIMIT ?2
+            )",
+            params![exec, MAX_LAUNCHES as i64],
+        )
+        .map_err(|e| db_error(&path, e))?;
+        tx.commit().map_err(|e| db_error(&path, e))
+    }
+    pub fn launches(&self) -> Result<HashMap<String, LaunchHistory>, SherlockError> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT exec, time FROM launches ORDER BY time")
+            .map_err(|e| db_error(&self.path, e))?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as u64))
+            })
+            .map_err(|e| db_error(&self.path, e))?;
+        let mut launches: HashMap<String, LaunchHistory> = HashMap::new();
+        for row in rows {
+            let (exec, time) = row.map_err(|e| db_error(&self.path, e))?;
+            launches.entry(exec).or_default().launches.push(time);
+        }
+        Ok(launches)
+    }
+    /// Frecency scores of all launched execs
+    pub fn scores(&self, half_life: f32) -> Result<HashMap<String, f32>, SherlockError> {
+        let now = unix_now();
+        Ok(self
+            .launches()?
+            .into_iter()
+            .map(|(exec, history)| (exec, history.score(now, half_life)))
+            .collect())
+    }
+
+    /// Remembers that `exec` was picked for `query` and forgets stale picks
+    pub fn record_selection(
+        &mut self,
+        query: &str,
+        exec: &str,
+        now: u64,
+        half_life: f32,
+    ) -> Result<(), SherlockError> {
+        let path = self.path.clone();
+        let tx = self.conn.transaction().map_err(|e| db_error(&path, e))?;
+        tx.execute(
+            "INSERT INTO query_history (query, exec, count, last) VALUES (?1, ?2, 1, ?3)
+            ON CONFLICT (query, exec) DO UPDATE SET count = count + 1, last = excluded.last",
+            params![query, exec, now as i64],
+        )
+        .map_err(|e| db_error(&path, e))?;
+
+        let selections = {
+            let mut stmt = tx
+                .prepare("SELECT query, exec, count, last FROM query_history")
+                .map_err(|e| db_error(&path, e))?;
+            let rows = stmt
+                .query_map([], selection_from_row)
+                .map_err(|e| db_error(&path, e))?;
+            rows.collect::<Result<Vec<Selection>, _>>()
+                .map_err(|e| db_error(&path, e))?
+        };
+        for selection in forgotten(&selections, now, half_life) {
+            tx.execute(
+                "DELETE FROM query_history WHERE query = ?1 AND exec = ?2",
+                params![selection.query, selection.exec],
+            )
+            .map_err(|e| db_error(&path, e))?;
+        }
+        tx.commit().map_err(|e| db_error(&path, e))
+    }
+    /// Picks made for `prefix` or a query starting with it
+    pub fn selections(&self, prefix: &str) -> Result<Vec<Selection>, SherlockError> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT query, exec, count, last FROM query_history
+                WHERE substr(query, 1, length(?1)) = ?1",
+            )
+            .map_err(|e| db_error(&self.path, e))?;
+        let rows = stmt
+            .query_map(params![prefix], selection_from_row)
+            .map_err(|e| db_error(&self.path, e))?;
+        rows.collect::<Result<Vec<Selection>, _>>()
+            .map_err(|e| db_error(&self.path, e))
+    }
+
+    pub fn state(&self, launcher: &str, key: &str) -> Result<Option<String>, SherlockError> {
+        self.conn
+            .query_row(
+                "SELECT value FROM launcher_state WHERE launcher = ?1 AND key = ?2",
+                params![launcher, key],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| db_error(&self.path, e))
+    }
+    pub fn set_state(&self, launcher: &str, key: &str, value: &str) -> Result<(), SherlockError> {
+        self.conn
+            .execute(
+                "INSERT INTO launcher_state (launcher, key, value) VALUES (?1, ?2, ?3)
+                ON CONFLICT (launcher, key) DO UPDATE SET value = excluded.value",
+                params![launcher, key, value],
+            )
+            .map(|_| ())
+            .map_err(|e| db_error(&self.path, e))
+    }
+
+    /// Records a calculation and drops all but the `keep` newest ones. An
+    /// earlier entry of the same expression is replaced.
+    pub fn record_calculation(
+        &mut self,
+        calculation: &Calculation,
+        time: u64,
+        keep: usize,
+    ) -> Result<(), SherlockError> {
+        let path = self.path.clone();
+        let tx = self.conn.transaction().map_err(|e| db_error(&path, e))?;
+        tx.execute(
+            "DELETE FROM calc_history WHERE expression = ?1",
+            params![calculation.expression],
+        )
+        .map_err(|e| db_error(&path, e))?;
+        tx.execute(
+            "INSERT INTO calc_history (expression, result, time) VALUES (?1, ?2, ?3)",
+            params![calculation.expression, calculation.result, time as i64],
+        )
+        .map_err(|e| db_error(&path, e))?;
+        tx.execute(
+            "DELETE FROM calc_history WHERE id NOT IN (
+                SELECT id FROM calc_history ORDER BY time DESC, id DESC LIMIT ?1
+            )",
+            params![keep as i64],
+        )
+        .map_err(|e| db_error(&path, e))?;
+        tx.commit().map_err(|e| db_error(&path, e))
+    }
+    /// The `limit` newest calculations, newest first
+    pub fn calculations(&self, limit: usize) -> Result<Vec<Calculation>, SherlockError> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT expression, result FROM calc_history
+                ORDER BY time DESC, id DESC LIMIT ?1",
+            )
+            .map_err(|e| db_error(&self.path, e))?;
+        let rows = stmt
+            .query_map(params![limit as i64], |row| {
+                Ok(Calculation {
+                    expression: row.get(0)?,
+                    result: row.get(1)?,
+                })
+            })
+            .map_err(|e| db_error(&self.path, e))?;
+        rows.collect::<Result<Vec<Calculation>, _>>()
+            .map_err(|e| db_error(&self.path, e))
+    }
+
+    /// Imports `counts.json` and `queries.json` of older versions once
+    fn import_legacy_files(&mut self) -> Result<(), SherlockError> {
+        if self.state("sherlock", "legacy_imported")?.is_some() {
+            return Ok(());
+        }
+        if let Ok(home) = env::var("HOME") {
+            let dir = PathBuf::from(home).join(".sherlock");
+            let launches = read_legacy_counts(&dir.join("counts.json"));
+            let selections = read_legacy_queries(&dir.join("queries.json"));
+            self.import(launches, selections)?;
+        }
+        self.set_state("sherlock", "legacy_imported", &unix_now().to_string())
+    }
+    fn import(
+        &mut self,
+        launches: HashMap<String, LaunchHistory>,
+        selections: Vec<Selection>,
+    ) -> Result<(), SherlockError> {
+        let path = self.path.clone();
+        let tx = self.conn.transaction().map_err(|e| db_error(&path, e))?;
+        for (exec, history) in launches {
+            for time in history.launches {
+                tx.execute(
+                    "INSERT INTO launches (exec, time) VALUES (?1, ?2)",
+                    params![exec, time as i64],
+                )
+                .map_err(|e| db_error(&path, e))?;
+            }
+        }
+        for s in selections {
+            tx.execute(
+                "INSERT OR REPLACE INTO query_history (query, exec, count, last)
+                VALUES (?1, ?2, ?3, ?4)",
+                params![s.query, s.exec, s.count, s.last as i64],
+            )
+            .map_err(|e| db_error(&path, e))?;
+        }
+        tx.commit().map_err(|e| db_error(&path, e))
+    }
+}
+
+fn selection_from_row(row: &rusqlite::Row) -> rusqlite::Result<Selection> {
+    Ok(Selection {
+        query: row.get(0)?,
+        exec: row.get(1)?,
+        count: row.get(2)?,
+        last: row.get::<_, i64>(3)? as u64,
+    })
+}
+
+fn database_path() -> Result<PathBuf, SherlockError> {
+    let data_home = match env::var("XDG_DATA_HOME") {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => {
+            let home = env::var("HOME").map_err(|e| SherlockError {
+                error: SherlockErrorType::EnvVarNotFoundError("HOME".to_string()),
+                traceback: e.to_string(),
+            })?;
+            PathBuf::from(home).join(".local/share")
+        }
+    };
+    Ok(data_home.join("sherlock/sherlock.db"))
+}
+
+fn db_error(path: &Path, e: rusqlite::Error) -> SherlockError {
+    SherlockError {
+        error: SherlockErrorType::DatabaseError(path.to_path_buf()),
+        traceback: e.to_string(),
+    }
+}
+
+/// Reads the launch counts of older versions. Depending on the version, they
+/// hold either launch times or a plain count per exec.
+fn read_legacy_counts(path: &Path) -> HashMap<String, LaunchHistory> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(_) => return HashMap::new(),
+    };
+    if let Ok(launches) = serde_json::from_str(&content) {
+        return launches;
+    }
+    match serde_json::from_str::<HashMap<String, f32>>(&content) {
+        Ok(counts) => {
+            // Plain counts carry no dates, so they are dated to the last write
+            let time = fs::metadata(path)
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map_or_else(unix_now, |d| d.as_secs());
+            migrate_counts(counts, time)
+        }
+        Err(_) => HashMap::new(),
+    }
+}
+
+fn read_legacy_queries(path: &Path) -> Vec<Selection> {
+    #[derive(serde::Deserialize)]
+    struct QueryFile {
+        selections: Vec<Selection>,
+    }
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<QueryFile>(&content).ok())
+        .map_or_else(Vec::new, |file| file.selections)
+}
+
+#[test]
+fn test_database() {
+    let mut db = SherlockDatabase {
+        conn: Connection::open_in_memory().unwrap(),
+        path: PathBuf::from(":memory:"),
+    };
+    db.setup().unwrap();
+    // Migrating twice is a no-op
+    db.migrate().unwrap();
+
+    let legacy = migrate_counts(HashMap::from([("firefox".to_string(), 3.0)]), 100);
+    db.import(legacy, vec![]).unwrap();
+    (0..MAX_LAUNCHES).for_each(|i| db.record_launch("kitty", 200 + i as u64).unwrap());
+    db.record_launch("kitty", 1000).unwrap();
+
+    let launches = db.launches().unwrap();
+    assert_eq!(launches["firefox"].launches, vec![100; 3]);
+    assert_eq!(launches["kitty"].launches.len(), MAX_LAUNCHES);
+    assert_eq!(launches["kitty"].launches.last(), Some(&1000));
+    assert_eq!(launches["kitty"].launches.first(), Some(&201));
+
+    db.record_selection("term", "kitty", 1000, 0.0).unwrap();
+    db.record_selection("term", "kitty", 1000, 0.0).unwrap();
+    db.record_selection("x", "xterm", 1000, 0.0).unwrap();
+    let selections = db.selections("te").unwrap();
+    assert_eq!(selections.len(), 1);
+    assert_eq!(selections[0].count, 2);
+
+    assert_eq!(db.state("sherlock", "test").unwrap(), None);
+    db.set_state("sherlock", "test", "1").unwrap();
+    db.set_state("sherlock", "test", "2").unwrap();
+    assert_eq!(db.state("sherlock", "test").unwrap().as_deref(), Some("2"));
+
+    let calculation = |expression: &str, result: &str| Calculation {
+        expression: expression.to_string(),
+        result: result.to_string(),
+    };
+    db.record_calculation(&calculation("1+1", "2"), 100, 2)
+        .unwrap();
+    db.record_calculation(&calculation("r = 2", "2"), 101, 2)
+        .unwrap();
+    db.record_calculation(&calculation("pi*r^2", "12.566"), 102, 2)
+        .unwrap();
+    db.record_calculation(&calculation("r = 2", "2"), 103, 2)
+        .unwrap();
+    assert_eq!(
+        db.calculations(10).unwrap(),
+        vec![calculation("r = 2", "2"), calculation("pi*r^2", "12.566")]
+    );
+    assert_eq!(db.calculations(1).unwrap().len(), 1);
+}
@@ -0,0 +1,368 @@
+use std::env;
+use std::path::Path;
+
+/// A parsed `.desktop` file following the Desktop Entry Specification
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DesktopFile {
+    pub groups: Vec<DesktopGroup>,
+}
+
+/// A `[Group Name]` section and its keys in file order
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DesktopGroup {
+    pub name: String,
+    entries: Vec<(String, String)>,
+}
+
+impl DesktopFile {
+    /// Parses the file leniently. Comments, blank lines, keys outside of a
+    /// group and repeated keys or groups are skipped.
+    pub fn parse(content: &str) -> Self {
+        let mut groups: Vec<DesktopGroup> = Vec::new();
+        let mut current: Option<DesktopGroup> = None;
+        for line in content.lines() {
+            let line = line.trim_start();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line.starts_with('[') {
+                if let Some(end) = line.find(']') {
+                    groups.extend(current.take());
+                    let name = &line[1..end];
+                    // Only the first group of a name counts
+                    if !groups.iter().any(|g| g.name == name) {
+                        current = Some(DesktopGroup {
+                            name: name.to_string(),
+                            entries: Vec::new(),
+                        });
+                    }
+                }
+                continue;
+            }
+            if let (Some(group), Some((key, value))) = (current.as_mut(), line.split_once('=')) {
+                let key = key.trim_end();
+                if !key.is_empty() && group.raw(key).is_none() {
+                    group
+                        .entries
+                        .push((key.to_string(), value.trim_start().to_string()));
+                }
+            }
+        }
+        groups.extend(current);
+        DesktopFile { groups }
+    }
+    pub fn group(&self, name: &str) -> Option<&DesktopGroup> {
+        self.groups.iter().find(|g| g.name == name)
+    }
+    /// The main `[Desktop Entry]` group
+    pub fn entry(&self) -> Option<&DesktopGroup> {
+        self.group("Desktop Entry")
+    }
+}
+
+impl DesktopGroup {
+    /// The value as written in the file
+    pub fn raw(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+    /// The value with escape sequences resolved
+    pub fn string(&self, key: &str) -> Option<String> {
+        self.raw(key).map(unescape)
+    }
+    pub fn boolean(&self, key: &str) -> bool {
+        self.raw(key).map_or(false, |v| v.trim() == "true")
+    }
+    /// A `;` separated list. Escaped semicolons belong to the item.
+    pub fn list(&self, key: &str) -> Vec<String> {
+        self.raw(key).map_or_else(Vec::new, split_list)
+    }
+    /// The key of the best matching locale, like `Name[de_DE]`, or the plain key
+    fn localized_key(&self, key: &str, locales: &[Locale]) -> String {
+        locales
+            .iter()
+            .flat_map(|locale| locale.variants())
+            .map(|variant| format!("{}[{}]", key, variant))
+            .find(|localized| self.raw(localized).is_some())
+            .unwrap_or_else(|| key.to_string())
+    }
+    pub fn localized_string(&self, key: &str, locales: &[Locale]) -> Option<String> {
+        self.string(&self.localized_key(key, locales))
+    }
+    pub fn localized_list(&self, key: &str, locales: &[Locale]) -> Vec<String> {
+        self.list(&self.localized_key(key, locales))
+    }
+
+    /// Whether an application entry should be listed on one of the
+    /// `desktops` (as in `XDG_CURRENT_DESKTOP`)
+    pub fn is_shown(&self, desktops: &[String]) -> bool {
+        if self.raw("Type").map(str::trim) != Some("Application") {
+            return false;
+        }
+        if self.boolean("Hidden") || self.boolean("NoDisplay") {
+            return false;
+        }
+        let listed = |key: &str| {
+            self.list(key).iter().any(|d| {
+                desktops
+                    .iter()
+                    .any(|current| current.eq_ignore_ascii_case(d))
+            })
+        };
+        if self.raw("OnlyShowIn").is_some() && !listed("OnlyShowIn") {
+            return false;
+        }
+        if listed("NotShowIn") {
+            return false;
+        }
+        match self.string("TryExec") {
+            Some(try_exec) if !try_exec.trim().is_empty() => is_executable(try_exec.trim()),
+            _ => true,
+        }
+    }
+}
+
+/// A locale of the form `lang_COUNTRY.ENCODING@MODIFIER`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Locale {
+    pub lang: String,
+    pub country: Option<String>,
+    pub modifier: Option<String>,
+}
+
+impl Locale {
+    /// Parses a locale name. `C` and `POSIX` have no translations and yield None.
+    pub fn parse(name: &str) -> Option<Self> {
+        let (rest, modifier) = match name.split_once('@') {
+            Some((rest, modifier)) => (rest, Some(modifier.to_string())),
+            None => (name, None),
+        };
+        // The encoding is irrelevant for matching
+        let rest = rest.split('.').next().unwrap_or_default();
+        let (lang, country) = match rest.split_once('_') {
+            Some((lang, country)) => (lang, Some(country.to_string())),
+            None => (rest, None),
+        };
+        if lang.is_empty() || lang == "C" || lang == "POSIX" {
+            return None;
+        }
+        Some(Self {
+            lang: lang.to_string(),
+            country,
+            modifier,
+        })
+    }
+    /// Locale keys to look for, most specific first
+    pub fn variants(&self) -> Vec<String> {
+        let mut variants = Vec::with_capacity(4);
+        if let (Some(country), Some(modifier)) = (&self.country, &self.modifier) {
+            variants.push(format!("{}_{}@{}", self.lang, country, modifier));
+        }
+        if let Some(country) = &self.country {
+            variants.push(format!("{}_{}", self.lang, country));
+        }
+        if let Some(modifier) = &self.modifier {
+            variants.push(format!("{}@{}", self.lang, modifier));
+        }
+        variants.push(self.lang.clone());
+        variants
+    }
+}
+
+/// The user's locales by preference. The list in `LANGUAGE` comes first,
+/// followed by the first of `LC_ALL`, `LC_MESSAGES` and `LANG` that is set.
+pub fn current_locales() -> Vec<Locale> {
+    let var = |name: &str| env::var(name).ok().filter(|v| !v.is_empty());
+    let mut names: Vec<String> = var("LANGUAGE")
+        .map(|list| list.split(':').map(str::to_string).collect())
+        .unwrap_or_default();
+    names.extend(
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .find_map(|name| var(name)),
+    );
+    let mut locales: Vec<Locale> = Vec::new();
+    for locale in names.iter().filter_map(|name| Locale::parse(name)) {
+        if !locales.contains(&locale) {
+            locales.push(locale);
+        }
+    }
+    locales
+}
+
+/// Resolves the `\s`, `\n`, `\t`, `\r` and `\\` escape sequences
+pub fn unescape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => result.push(' '),
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some('\\') => result.push('\\'),
+            Some(other) => {
+                result.push('\\');
+                result.push(other);
+            }
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut item = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(';') => item.push(';'),
+                Some(other) => {
+                    item.push('\\');
+                    item.push(other);
+                }
+                None => item.push('\\'),
+            },
+            ';' => items.push(std::mem::take(&mut item)),
+            _ => item.push(c),
+        }
+    }
+    items.push(item);
+    items
+        .iter()
+        .map(|item| unescape(item))
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// The desktops named in `XDG_CURRENT_DESKTOP`
+pub fn current_desktops() -> Vec<String> {
+    env::var("XDG_CURRENT_DESKTOP")
+        .map(|v| {
+            v.split(':')
+                .filter(|d| !d.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn is_executable(program: &str) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    let executable = |path: &Path| {
+        path.metadata().map_or(false, |m| {
+            m.is_file() && m.permissions().mode() & 0o111 != 0
+        })
+    };
+    if program.contains('/') {
+        return executable(Path::new(program));
+    }
+    env::var_os("PATH").map_or(false, |paths| {
+        env::split_paths(&paths).any(|dir| executable(&dir.join(program)))
+    })
+}
+
+#[test]
+fn test_parse_desktop_file() {
+    let file = DesktopFile::parse(include_str!("../../tests/fixtures/desktop/firefox.desktop"));
+    let entry = file.entry().unwrap();
+    assert_eq!(entry.string("Name").as_deref(), Some("Firefox"));
+    assert_eq!(entry.raw("Name[de]"), Some("Firefox-Webbrowser"));
+    assert_eq!(entry.string("Exec").as_deref(), Some("firefox %u"));
+    assert_eq!(entry.string("Icon").as_deref(), Some("firefox"));
+    assert_eq!(
+        entry.list("Keywords"),
+        vec!["Internet", "WWW", "Browser", "Web;Explorer"]
+    );
+    assert!(!entry.boolean("Terminal"));
+    assert!(entry.is_shown(&[]));
+
+    // Keys of action groups don't leak into the entry
+    assert_eq!(file.groups.len(), 3);
+    let private = file.group("Desktop Action new-private-window").unwrap();
+    assert_eq!(
+        private.string("Exec").as_deref(),
+        Some("firefox --private-window %u")
+    );
+
+    let escapes = DesktopFile::parse(include_str!("../../tests/fixtures/desktop/escapes.desktop"));
+    let entry = escapes.entry().unwrap();
+    assert_eq!(entry.string("Name").as_deref(), Some("Escape Tester"));
+    assert_eq!(
+        entry.string("Comment").as_deref(),
+        Some("Tabs\tand\nlines, a back\\slash and spaces")
+    );
+    assert_eq!(entry.string("Path").as_deref(), Some("/tmp/work dir"));
+    assert_eq!(entry.list("Categories"), vec!["Utility", "Development"]);
+}
+
+#[test]
+fn test_localized_values() {
+    let file = DesktopFile::parse(include_str!("../../tests/fixtures/desktop/firefox.desktop"));
+    let entry = file.entry().unwrap();
+    let name = |locale: &str| {
+        let locales: Vec<Locale> = Locale::parse(locale).into_iter().collect();
+        entry.localized_string("Name", &locales).unwrap()
+    };
+    assert_eq!(name("de_AT.UTF-8@euro"), "Firefox-Webbrowser");
+    assert_eq!(name("fr_FR.UTF-8"), "Navigateur Web Firefox");
+    assert_eq!(name("pt_BR"), "Navegador Firefox");
+    assert_eq!(name("pt_PT"), "Firefox");
+    assert_eq!(name("sr_RS@latin"), "Firefox pregledač");
+    assert_eq!(name("C"), "Firefox");
+
+    // Earlier locales win, missing translations fall back to later ones
+    let locales = vec![
+        Locale::parse("es_ES").unwrap(),
+        Locale::parse("de_DE").unwrap(),
+    ];
+    assert_eq!(
+        entry.localized_string("GenericName", &locales).as_deref(),
+        Some("Webbrowser")
+    );
+    assert_eq!(
+        entry.localized_list("Keywords", &locales),
+        vec!["Internet", "WWW", "Browser", "Web", "Surfen"]
+    );
+    assert_eq!(
+        entry.localized_string("Comment", &locales).as_deref(),
+        Some("Browse the World Wide Web")
+    );
+    assert_eq!(
+        Locale::parse("sr_RS.UTF-8@latin").unwrap().variants(),
+        vec!["sr_RS@latin", "sr_RS", "sr@latin", "sr"]
+    );
+}
+
+#[test]
+fn test_desktop_file_visibility() {
+    let shown = |fixture: &str, desktops: &[&str]| {
+        let desktops: Vec<String> = desktops.iter().map(|d| d.to_string()).collect();
+        DesktopFile::parse(fixture)
+            .entry()
+            .map_or(false, |entry| entry.is_shown(&desktops))
+    };
+    let hidden = include_str!("../../tests/fixtures/desktop/hidden.desktop");
+    let link = include_str!("../../tests/fixtures/desktop/link.desktop");
+    let only_kde = include_str!("../../tests/fixtures/desktop/only-kde.desktop");
+    let not_gnome = include_str!("../../tests/fixtures/desktop/not-gnome.desktop");
+    let try_exec = include_str!("../../tests/fixtures/desktop/missing-tryexec.desktop");
+
+    assert!(!shown(hidden, &[]));
+    assert!(!shown(link, &[]));
+    assert!(shown(only_kde, &["KDE"]));
+    assert!(!shown(only_kde, &["GNOME"]));
+    assert!(!shown(only_kde, &[]));
+    assert!(shown(not_gnome, &["Hyprland"]));
+    assert!(!shown(not_gnome, &["ubuntu", "GNOME"]));
+    assert!(!shown(try_exec, &[]));
+    assert!(!shown("Name=No Group\nType=Application", &[]));
+}
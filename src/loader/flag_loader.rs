@@ -51,6 +51,8 @@ impl SherlockFlags {
             style: extract_path_value("--style"),
             ignore: extract_path_value("--ignore"),
             alias: extract_path_value("--alias"),
+            socket: extract_path_value("--socket"),
+            lock: extract_path_value("--lock"),
             display_raw: check_flag_existance("--display-raw"),
             center_raw: check_flag_existance("--center"),
             cache: extract_path_value("--cache"),
@@ -83,6 +85,8 @@ pub fn print_help() -> Result<(), SherlockError> {
             "Force Sherlock to use a singular tile to display the piped content",
         ),
         ("--cache", "Specify the sherlock cache file (.json)."),
+        ("--socket", "Specify the location of the daemon socket."),
+        ("--lock", "Specify the location of the lock file."),
         (
             "--daemonize",
             "If this flag is set, sherlock will run in daemon mode.",
@@ -91,6 +95,10 @@ pub fn print_help() -> Result<(), SherlockError> {
             "--method",
             "For pipe mode only: Specifies what to do with the selected data row",
         ),
+        (
+            "msg",
+            "Send a command to the running daemon. Run 'sherlock msg' for details.",
+        ),
     ];
 
     // Print header
@@ -0,0 +1,106 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::database::SherlockDatabase;
+use super::util::{default_frecency_half_life, SherlockError};
+use crate::CONFIG;
+
+// Older launches barely contribute to the score, so they are dropped
+pub const MAX_LAUNCHES: usize = 64;
+
+/// Launch times of a single exec as unix timestamps, oldest first
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct LaunchHistory {
+    pub launches: Vec<u64>,
+}
+
+impl LaunchHistory {
+    /// Sum of all launches, each weighted down by half every `half_life` seconds
+    pub fn score(&self, now: u64, half_life: f32) -> f32 {
+        if half_life <= 0.0 {
+            return self.launches.len() as f32;
+        }
+        self.launches
+            .iter()
+            .map(|time| {
+                let age = now.saturating_sub(*time) as f32;
+                0.5f32.powf(age / half_life)
+            })
+            .sum()
+    }
+}
+
+/// Turns the plain launch counts of older versions into launch histories.
+/// Their launches are dated to `time`, the last time the counts were written.
+pub fn migrate_counts(counts: HashMap<String, f32>, time: u64) -> HashMap<String, LaunchHistory> {
+    counts
+        .into_iter()
+        .map(|(exec, count)| {
+            let n = (count.max(0.0) as usize).min(MAX_LAUNCHES);
+            (
+                exec,
+                LaunchHistory {
+                    launches: vec![time; n],
+                },
+            )
+        })
+        .collect()
+}
+
+/// Priority of an entry within its launcher. Frequently and recently launched
+/// entries approach `priority`, unused ones are placed at `priority + 1`.
+pub fn frecency_priority(priority: f32, score: f32) -> f32 {
+    priority + 1.0 - score / (score + 1.0)
+}
+
+/// Frecency scores of all launched execs
+pub fn launch_scores() -> Result<HashMap<String, f32>, SherlockError> {
+    SherlockDatabase::with(|db| db.scores(half_life()))
+}
+
+pub fn record_launch(exec: &str) -> Result<(), SherlockError> {
+    SherlockDatabase::with(|db| db.record_launch(exec, unix_now()))
+}
+
+/// The configured half-life in seconds
+pub fn half_life() -> f32 {
+    CONFIG.get().map_or_else(default_frecency_half_life, |c| {
+        c.behavior.frecency_half_life
+    }) * 86400.0
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+#[test]
+fn test_frecency() {
+    const DAY: u64 = 86400;
+    let now = 400 * DAY;
+    let half_life = 7.0 * DAY as f32;
+
+    // Heavy use long ago loses against daily use now
+    let old = migrate_counts(HashMap::from([("old".to_string(), 500.0)]), now - 365 * DAY);
+    let recent = LaunchHistory {
+        launches: (0..5).map(|i| now - i * DAY).collect(),
+    };
+    assert_eq!(old["old"].launches.len(), MAX_LAUNCHES);
+    assert!(recent.score(now, half_life) > old["old"].score(now, half_life));
+
+    // A launch loses half of its weight every half life
+    let single = LaunchHistory {
+        launches: vec![now - 7 * DAY],
+    };
+    assert!((single.score(now, half_life) - 0.5).abs() < 1e-4);
+
+    // Priorities stay within the launcher's priority band
+    assert_eq!(frecency_priority(2.0, 0.0), 3.0);
+    assert!(frecency_priority(2.0, 3.0) < frecency_priority(2.0, 1.0));
+    assert!(frecency_priority(2.0, 1000.0) > 2.0);
+
+    let migrated = migrate_counts(HashMap::from([("firefox".to_string(), 3.0)]), now);
+    assert_eq!(migrated["firefox"].launches, vec![now; 3]);
+}
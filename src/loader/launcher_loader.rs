@@ -3,14 +3,14 @@ use rayon::iter::ParallelIterator;
 use serde_json::Value;
 use std::collections::{HashMap, HashSet};
 
-use std::env;
-use std::fs::{self, File};
+use std::fs::File;
 use std::path::PathBuf;
 
 use crate::actions::util::read_from_clipboard;
 use crate::launcher::audio_launcher::AudioLauncherFunctions;
 use crate::launcher::calc_launcher::Calculator;
 use crate::launcher::category_launcher::CategoryLauncher;
+use crate::launcher::currency::refresh_rates;
 use crate::launcher::event_launcher::EventLauncher;
 use crate::launcher::process_launcher::ProcessLauncher;
 use crate::launcher::weather_launcher::WeatherLauncher;
@@ -27,7 +27,7 @@ use simd_json::prelude::ArrayTrait;
 use system_cmd_launcher::SystemCommand;
 use web_launcher::Web;
 
-use super::util::parse_priority;
+use super::frecency::{frecency_priority, launch_scores};
 use super::{
     util::{self, SherlockError, SherlockErrorType},
     Loader,
@@ -37,14 +37,17 @@ use util::{AppData, RawLauncher};
 
 impl Loader {
     pub async fn load_launchers() -> Result<(Vec<Launcher>, Vec<SherlockError>), SherlockError> {
-        wrapped()
+        let (launchers, n) = wrapped()?;
+        Ok((launchers.into_iter().map(|(_, l)| l).collect(), n))
     }
-    pub fn load_launchers_sync() -> Result<(Vec<Launcher>, Vec<SherlockError>), SherlockError> {
+    /// Loads the launchers together with the configuration they were built from
+    pub fn load_launcher_sources(
+    ) -> Result<(Vec<(RawLauncher, Launcher)>, Vec<SherlockError>), SherlockError> {
         wrapped()
     }
 }
 
-fn wrapped() -> Result<(Vec<Launcher>, Vec<SherlockError>), SherlockError> {
+fn wrapped() -> Result<(Vec<(RawLauncher, Launcher)>, Vec<SherlockError>), SherlockError> {
     let config = CONFIG.get().ok_or_else(|| SherlockError {
         error: SherlockErrorType::ConfigError(None),
         traceback: String::new(),
@@ -53,269 +56,220 @@ fn wrapped() -> Result<(Vec<Launcher>, Vec<SherlockError>), SherlockError> {
     // Read fallback data here:
     let (launcher_config, n) = parse_launcher_configs(&config.files.fallback)?;
 
-    // Read cached counter file
-    let counter_reader = CounterReader::new()?;
-    let counts = counter_reader.read()?;
+    // Read the launch history. Without it, everything keeps its plain priority.
+    let mut non_breaking: Vec<SherlockError> = Vec::new();
+    let scores = launch_scores()
+        .map_err(|e| non_breaking.push(e))
+        .unwrap_or_default();
 
     // Parse the launchers
-    let deserialized_launchers: Vec<Result<Launcher, SherlockError>> = launcher_config
-        .into_par_iter()
-        .map(|cmd| {
-            let counts_clone = counts.clone();
-            let max_decimals = counts_clone
-                .iter()
-                .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
-                .map(|(_, v)| v.to_string().len())
-                .unwrap_or(0) as i32;
-            let launcher_type: LauncherType = match cmd.r#type.as_str() {
-                "categories" => {
-                    let prio = cmd.priority;
-                    let mut categories: HashMap<String, AppData> =
-                        serde_json::from_value(cmd.args["categories"].clone()).unwrap_or_default();
-                    categories.iter_mut().for_each(|(_, v)| {
-                        v.priority = match counts_clone.get(&v.exec) {
-                            Some(c) if c == &0.0 => prio,
-                            Some(c) => parse_priority(prio, *c as f32, max_decimals),
-                            _ => prio,
-                        };
-                    });
-                    LauncherType::CategoryLauncher(CategoryLauncher { categories })
-                }
-                "app_launcher" => {
-                    let mut apps: HashMap<String, AppData> = HashMap::new();
-                    if let Some(c) = CONFIG.get() {
-                        apps = match c.behavior.caching {
-                            true => Loader::load_applications(
-                                cmd.priority as f32,
-                                counts_clone,
-                                max_decimals,
-                            )?,
-                            false => Loader::load_applications_from_disk(
-                                None,
-                                cmd.priority as f32,
-                                counts_clone,
-                                max_decimals,
-                            )?,
-                        };
-                    }
-
-                    LauncherType::App(App { apps })
-                }
-                "web_launcher" => LauncherType::Web(Web {
-                    display_name: cmd.display_name.clone().unwrap_or("".to_string()),
-                    icon: cmd.args["icon"].as_str().unwrap_or_default().to_string(),
-                    engine: cmd.args["search_engine"]
-                        .as_str()
-                        .unwrap_or_default()
-                        .to_string(),
-                }),
-                "calculation" => {
-                    let capabilities: Option<HashSet<String>> = match cmd.args.get("capabilities") {
-                        Some(Value::Array(arr)) => {
-                            let strings: HashSet<String> = arr
-                                .iter()
-                                .filter_map(|v| v.as_str().map(|s| s.to_string()))
-                                .collect();
-                            Some(strings)
-                        }
-                        _ => None,
-                    };
-                    LauncherType::Calc(Calculator { capabilities })
-                }
-                "command" => {
-                    let prio = cmd.priority;
-                    let mut commands: HashMap<String, AppData> =
-                        serde_json::from_value(cmd.args["commands"].clone()).unwrap_or_default();
-                    commands.iter_mut().for_each(|(_, v)| {
-                        v.priority = match counts_clone.get(&v.exec) {
-                            Some(c) if c == &0.0 => prio,
-                            Some(c) => parse_priority(prio, *c as f32, max_decimals),
-                            _ => prio,
-                        };
-                    });
-                    LauncherType::SystemCommand(SystemCommand { commands })
-                }
-                "bulk_text" => LauncherType::BulkText(BulkText {
-                    icon: cmd.args["icon"].as_str().unwrap_or_default().to_string(),
-                    exec: cmd.args["exec"].as_str().unwrap_or_default().to_string(),
-                    args: cmd.args["exec-args"]
-                        .as_str()
-                        .unwrap_or_default()
-                        .to_string(),
-                }),
-                "clipboard-execution" => {
-                    let clipboard_content: String = read_from_clipboard()?;
-                    let capabilities: Option<HashSet<String>> = match cmd.args.get("capabilities") {
-                        Some(Value::Array(arr)) => {
-                            let strings: HashSet<String> = arr
-                                .iter()
-                                .filter_map(|v| v.as_str().map(|s| s.to_string()))
-                                .collect();
-                            Some(strings)
-                        }
-                        _ => None,
-                    };
-                    if clipboard_content.is_empty() {
-                        LauncherType::Empty
-                    } else {
-                        LauncherType::Clipboard((
-                            ClipboardLauncher {
-                                clipboard_content,
-                                capabilities: capabilities.clone(),
-                            },
-                            Calculator { capabilities },
-                        ))
-                    }
-                }
-                "teams_event" => {
-                    let icon = cmd.args["icon"].as_str().unwrap_or("teams").to_string();
-                    let date = cmd.args["event_date"].as_str().unwrap_or("now");
-                    let event_start = cmd.args["event_start"].as_str().unwrap_or("-5 minutes");
-                    let event_end = cmd.args["event_end"].as_str().unwrap_or("+15 minutes");
-
-                    let event = EventLauncher::get_event(date, event_start, event_end);
-
-                    LauncherType::EventLauncher(EventLauncher { event, icon })
-                }
-                "audio_sink" => AudioLauncherFunctions::new()
-                    .and_then(|launcher| {
-                        launcher.get_current_player().and_then(|player| {
-                            launcher.get_metadata(&player).and_then(|launcher| {
-                                Some(LauncherType::MusicPlayerLauncher(launcher))
-                            })
-                        })
-                    })
-                    .unwrap_or(LauncherType::Empty),
-                "process" => {
-                    let icon = cmd.args["icon"].as_str().unwrap_or("sherlock-process");
-                    let launcher = ProcessLauncher::new(icon);
-                    if let Some(launcher) = launcher {
-                        LauncherType::ProcessLauncher(launcher)
-                    } else {
-                        LauncherType::Empty
-                    }
-                }
-                "weather" => {
-                    if let Some(location) = cmd.args["location"].as_str() {
-                        let update_interval = cmd.args["update_interval"].as_u64().unwrap_or(60);
-                        LauncherType::WeatherLauncher(WeatherLauncher {
-                            location: location.to_string(),
-                            update_interval,
-                        })
-                    } else {
-                        LauncherType::Empty
-                    }
-                }
-                "debug" => {
-                    let prio = cmd.priority;
-                    let mut commands: HashMap<String, AppData> =
-                        serde_json::from_value(cmd.args["commands"].clone()).unwrap_or_default();
-                    commands.iter_mut().for_each(|(_, v)| {
-                        v.priority = match counts_clone.get(&v.exec) {
-                            Some(c) if c == &0.0 => prio,
-                            Some(c) => parse_priority(prio, *c as f32, max_decimals),
-                            _ => prio,
-                        };
-                    });
-                    LauncherType::SystemCommand(SystemCommand { commands })
-                }
-                _ => LauncherType::Empty,
-            };
-            let method: String = if let Some(value) = &cmd.on_return {
-                value.to_string()
-            } else {
-                cmd.r#type.clone()
-            };
-            Ok(Launcher {
-                name: cmd.name,
-                alias: cmd.alias,
-                tag_start: cmd.tag_start,
-                tag_end: cmd.tag_end,
-                method,
-                next_content: cmd.next_content,
-                priority: cmd.priority as u32,
-                r#async: cmd.r#async,
-                home: cmd.home,
-                only_home: cmd.only_home,
-                launcher_type,
-                shortcut: cmd.shortcut,
-                spawn_focus: cmd.spawn_focus,
+    let deserialized_launchers: Vec<Result<(RawLauncher, Launcher), SherlockError>> =
+        launcher_config
+            .into_par_iter()
+            .map(|cmd| {
+                let launcher = build_launcher(cmd.clone(), &scores)?;
+                Ok((cmd, launcher))
             })
-        })
-        .collect();
+            .collect();
 
     // Get errors and launchers
     let (oks, errs): (Vec<_>, Vec<_>) = deserialized_launchers.into_iter().partition(Result::is_ok);
-    let launchers: Vec<Launcher> = oks.into_iter().filter_map(Result::ok).collect();
-    let mut non_breaking: Vec<SherlockError> = errs.into_iter().filter_map(Result::err).collect();
-    if counts.is_empty() {
-        let counts: HashMap<String, f32> = launchers
-            .iter()
-            .filter_map(|launcher| launcher.get_execs())
-            .flat_map(|exec_set| exec_set.into_iter().map(|exec| (exec, 0.0)))
-            .collect();
-        if let Err(e) = counter_reader.write(counts) {
-            non_breaking.push(e)
-        };
-    }
+    let launchers: Vec<(RawLauncher, Launcher)> = oks.into_iter().filter_map(Result::ok).collect();
+    non_breaking.extend(errs.into_iter().filter_map(Result::err));
     non_breaking.extend(n);
     Ok((launchers, non_breaking))
 }
 
-pub struct CounterReader {
-    path: PathBuf,
-}
-impl CounterReader {
-    pub fn new() -> Result<Self, SherlockError> {
-        let home = env::var("HOME").map_err(|e| SherlockError {
-            error: SherlockErrorType::EnvVarNotFoundError("HOME".to_string()),
-            traceback: e.to_string(),
-        })?;
-        let home_dir = PathBuf::from(home);
-        let path = home_dir.join(".sherlock/counts.json");
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent).map_err(|e| SherlockError {
-                error: SherlockErrorType::DirCreateError(".sherlock".to_string()),
-                traceback: e.to_string(),
-            })?;
+pub fn build_launcher(
+    cmd: RawLauncher,
+    scores: &HashMap<String, f32>,
+) -> Result<Launcher, SherlockError> {
+    let launcher_type: LauncherType = match cmd.r#type.as_str() {
+        "categories" => {
+            let prio = cmd.priority;
+            let mut categories: HashMap<String, AppData> =
+                serde_json::from_value(cmd.args["categories"].clone()).unwrap_or_default();
+            apply_scores(&mut categories, prio, scores);
+            LauncherType::CategoryLauncher(CategoryLauncher { categories })
         }
-        Ok(CounterReader { path })
-    }
-    pub fn write(&self, counts: HashMap<String, f32>) -> Result<(), SherlockError> {
-        let tmp_path = self.path.with_extension(".tmp");
-        if let Ok(f) = File::create(&tmp_path) {
-            if let Ok(_) = simd_json::to_writer(f, &counts) {
-                let _ = fs::rename(&tmp_path, &self.path);
+        "app_launcher" => {
+            let mut apps: HashMap<String, AppData> = HashMap::new();
+            if let Some(c) = CONFIG.get() {
+                apps = match c.behavior.caching {
+                    true => Loader::load_applications(cmd.priority as f32, scores.clone())?,
+                    false => Loader::load_applications_from_disk(
+                        None,
+                        cmd.priority as f32,
+                        scores.clone(),
+                    )?,
+                };
+            }
+
+            LauncherType::App(App { apps })
+        }
+        "web_launcher" => LauncherType::Web(Web {
+            priority: web_priority(&cmd, scores),
+            display_name: cmd.display_name.clone().unwrap_or("".to_string()),
+            icon: cmd.args["icon"].as_str().unwrap_or_default().to_string(),
+            engine: cmd.args["search_engine"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+        }),
+        "calculation" => {
+            let capabilities = capabilities(&cmd);
+            refresh_currency_rates(&cmd);
+            LauncherType::Calc(Calculator { capabilities })
+        }
+        "command" => {
+            let prio = cmd.priority;
+            let mut commands: HashMap<String, AppData> =
+                serde_json::from_value(cmd.args["commands"].clone()).unwrap_or_default();
+            apply_scores(&mut commands, prio, scores);
+            LauncherType::SystemCommand(SystemCommand { commands })
+        }
+        "bulk_text" => LauncherType::BulkText(BulkText {
+            icon: cmd.args["icon"].as_str().unwrap_or_default().to_string(),
+            exec: cmd.args["exec"].as_str().unwrap_or_default().to_string(),
+            args: cmd.args["exec-args"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+        }),
+        "clipboard-execution" => {
+            let clipboard_content: String = read_from_clipboard()?;
+            let capabilities = capabilities(&cmd);
+            refresh_currency_rates(&cmd);
+            if clipboard_content.is_empty() {
+                LauncherType::Empty
             } else {
-                let _ = fs::remove_file(&tmp_path);
+                LauncherType::Clipboard((
+                    ClipboardLauncher {
+                        clipboard_content,
+                        capabilities: capabilities.clone(),
+                    },
+                    Calculator { capabilities },
+                ))
             }
         }
-        Ok(())
-    }
-    pub fn read(&self) -> Result<HashMap<String, f32>, SherlockError> {
-        let file = if self.path.exists() {
-            File::open(&self.path)
-        } else {
-            File::create(&self.path)
+        "teams_event" => {
+            let icon = cmd.args["icon"].as_str().unwrap_or("teams").to_string();
+            let date = cmd.args["event_date"].as_str().unwrap_or("now");
+            let event_start = cmd.args["event_start"].as_str().unwrap_or("-5 minutes");
+            let event_end = cmd.args["event_end"].as_str().unwrap_or("+15 minutes");
+
+            let event = EventLauncher::get_event(date, event_start, event_end);
+
+            LauncherType::EventLauncher(EventLauncher { event, icon })
         }
-        .map_err(|e| SherlockError {
-            error: SherlockErrorType::FileExistError(self.path.clone()),
-            traceback: e.to_string(),
-        })?;
-        let counts = match simd_json::from_reader(file).ok() {
-            Some(c) => c,
-            _ => HashMap::new(),
-        };
+        "audio_sink" => AudioLauncherFunctions::new()
+            .and_then(|launcher| {
+                launcher.get_current_player().and_then(|player| {
+                    launcher
+                        .get_metadata(&player)
+                        .and_then(|launcher| Some(LauncherType::MusicPlayerLauncher(launcher)))
+                })
+            })
+            .unwrap_or(LauncherType::Empty),
+        "process" => {
+            let icon = cmd.args["icon"].as_str().unwrap_or("sherlock-process");
+            let launcher = ProcessLauncher::new(icon);
+            if let Some(launcher) = launcher {
+                LauncherType::ProcessLauncher(launcher)
+            } else {
+                LauncherType::Empty
+            }
+        }
+        "weather" => {
+            if let Some(location) = cmd.args["location"].as_str() {
+                let update_interval = cmd.args["update_interval"].as_u64().unwrap_or(60);
+                LauncherType::WeatherLauncher(WeatherLauncher {
+                    location: location.to_string(),
+                    update_interval,
+                })
+            } else {
+                LauncherType::Empty
+            }
+        }
+        "debug" => {
+            let prio = cmd.priority;
+            let mut commands: HashMap<String, AppData> =
+                serde_json::from_value(cmd.args["commands"].clone()).unwrap_or_default();
+            apply_scores(&mut commands, prio, scores);
+            LauncherType::SystemCommand(SystemCommand { commands })
+        }
+        _ => LauncherType::Empty,
+    };
+    let method: String = if let Some(value) = &cmd.on_return {
+        value.to_string()
+    } else {
+        cmd.r#type.clone()
+    };
+    Ok(Launcher {
+        name: cmd.name,
+        alias: cmd.alias,
+        tag_start: cmd.tag_start,
+        tag_end: cmd.tag_end,
+        method,
+        next_content: cmd.next_content,
+        priority: cmd.priority as u32,
+        r#async: cmd.r#async,
+        home: cmd.home,
+        only_home: cmd.only_home,
+        launcher_type,
+        shortcut: cmd.shortcut,
+        spawn_focus: cmd.spawn_focus,
+    })
+}
+
+fn capabilities(raw: &RawLauncher) -> Option<HashSet<String>> {
+    match raw.args.get("capabilities") {
+        Some(Value::Array(arr)) => Some(
+            arr.iter()
+                .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                .collect(),
+        ),
+        _ => None,
+    }
+}
 
-        Ok(counts)
+/// Keeps the exchange rates up to date for launchers converting currencies.
+/// Rates are fetched every 12 hours unless `currency_update_interval` says
+/// otherwise. An interval of 0 only uses the cached rates.
+pub fn refresh_currency_rates(raw: &RawLauncher) {
+    let enabled = capabilities(raw).is_some_and(|c| c.contains("calc.currencies"));
+    let interval = raw.args["currency_update_interval"].as_u64().unwrap_or(720);
+    if enabled && interval > 0 {
+        refresh_rates(interval);
     }
-    pub fn increment(&self, key: &str) -> Result<(), SherlockError> {
-        let mut content = self.read()?;
-        if let Some(value) = content.get_mut(key) {
-            *value += 1.0;
-        };
-        self.write(content)?;
-        Ok(())
+}
+
+fn apply_scores(entries: &mut HashMap<String, AppData>, prio: f32, scores: &HashMap<String, f32>) {
+    entries.iter_mut().for_each(|(_, v)| {
+        let score = scores.get(&v.exec).copied().unwrap_or(0.0);
+        v.priority = frecency_priority(prio, score);
+    });
+}
+
+fn web_priority(raw: &RawLauncher, scores: &HashMap<String, f32>) -> f32 {
+    let engine = raw.args["search_engine"].as_str().unwrap_or_default();
+    let score = scores
+        .get(&format!("websearch-{}", engine))
+        .copied()
+        .unwrap_or(0.0);
+    frecency_priority(raw.priority, score)
+}
+
+/// Updates the priorities of a launcher's entries to new frecency scores
+pub fn reprioritize(launcher: &mut Launcher, raw: &RawLauncher, scores: &HashMap<String, f32>) {
+    match &mut launcher.launcher_type {
+        LauncherType::App(app) => apply_scores(&mut app.apps, raw.priority, scores),
+        LauncherType::CategoryLauncher(ctg) => {
+            apply_scores(&mut ctg.categories, raw.priority, scores)
+        }
+        LauncherType::SystemCommand(cmd) => apply_scores(&mut cmd.commands, raw.priority, scores),
+        LauncherType::Web(web) => web.priority = web_priority(raw, scores),
+        _ => {}
     }
 }
 
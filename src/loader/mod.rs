@@ -1,10 +1,15 @@
 pub mod application_loader;
+pub mod calc_history;
 pub mod config_loader;
 pub mod css_loader;
+pub mod database;
+pub mod desktop_entry;
 pub mod flag_loader;
+pub mod frecency;
 pub mod icon_loader;
 pub mod launcher_loader;
 pub mod pipe_loader;
+pub mod query_memory;
 pub mod resource_loader;
 pub mod util;
 
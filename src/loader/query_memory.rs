@@ -0,0 +1,137 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::database::SherlockDatabase;
+use super::frecency::{half_life, unix_now};
+use super::util::SherlockError;
+
+// Upper bound of remembered (query, exec) pairs
+const MAX_SELECTIONS: usize = 1000;
+// Selections whose weight decayed below this are forgotten
+const MIN_WEIGHT: f32 = 0.05;
+
+/// An exec that was chosen after typing `query`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Selection {
+    pub query: String,
+    pub exec: String,
+    pub count: u32,
+    pub last: u64,
+}
+
+impl Selection {
+    fn weight(&self, now: u64, half_life: f32) -> f32 {
+        if half_life <= 0.0 {
+            return self.count as f32;
+        }
+        let age = now.saturating_sub(self.last) as f32;
+        self.count as f32 * 0.5f32.powf(age / half_life)
+    }
+}
+
+/// Selections to drop: those that faded out and the weakest ones beyond the cap
+pub fn forgotten(selections: &[Selection], now: u64, half_life: f32) -> Vec<&Selection> {
+    let mut weighted: Vec<(f32, &Selection)> = selections
+        .iter()
+        .map(|s| (s.weight(now, half_life), s))
+        .collect();
+    weighted.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+    weighted
+        .into_iter()
+        .enumerate()
+        .filter(|(i, (weight, _))| *i >= MAX_SELECTIONS || *weight < MIN_WEIGHT)
+        .map(|(_, (_, s))| s)
+        .collect()
+}
+
+/// Weight per exec of the selections made for `query` or a longer query
+/// starting with it. Selections for longer queries count proportionally less.
+pub fn boosts(
+    selections: &[Selection],
+    query: &str,
+    now: u64,
+    half_life: f32,
+) -> HashMap<String, f32> {
+    let query = normalize(query);
+    let mut boosts: HashMap<String, f32> = HashMap::new();
+    if query.is_empty() {
+        return boosts;
+    }
+    let len = query.chars().count() as f32;
+    for selection in selections.iter() {
+        if selection.query.starts_with(&query) {
+            let specificity = len / selection.query.chars().count() as f32;
+            *boosts.entry(selection.exec.clone()).or_default() +=
+                selection.weight(now, half_life) * specificity;
+        }
+    }
+    boosts
+}
+
+fn normalize(query: &str) -> String {
+    query
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Remembers that `exec` was chosen for `query`
+pub fn remember_selection(query: &str, exec: &str) -> Result<(), SherlockError> {
+    let query = normalize(query);
+    if query.is_empty() || exec.is_empty() {
+        return Ok(());
+    }
+    SherlockDatabase::with(|db| db.record_selection(&query, exec, unix_now(), half_life()))
+}
+
+/// Boosts of the execs previously chosen for `query`
+pub fn selection_boosts(query: &str) -> HashMap<String, f32> {
+    let query = normalize(query);
+    if query.is_empty() {
+        return HashMap::new();
+    }
+    SherlockDatabase::with(|db| db.selections(&query)).map_or_else(
+        |_| HashMap::new(),
+        |selections| boosts(&selections, &query, unix_now(), half_life()),
+    )
+}
+
+#[test]
+fn test_query_memory() {
+    const DAY: u64 = 86400;
+    let half_life = 7.0 * DAY as f32;
+    let now = 400 * DAY;
+    let selection = |query: &str, exec: &str, count: u32, last: u64| Selection {
+        query: query.to_string(),
+        exec: exec.to_string(),
+        count,
+        last,
+    };
+    let selections = vec![
+        selection("term", "kitty", 2, now),
+        selection("t", "thunderbird", 1, now),
+    ];
+
+    // Same or longer queries are boosted, the exact one the most
+    let te = boosts(&selections, " Te", now, half_life);
+    assert_eq!(te.len(), 1);
+    assert!((te["kitty"] - 1.0).abs() < 1e-4);
+    assert!(boosts(&selections, "term", now, half_life)["kitty"] > te["kitty"]);
+    assert_eq!(boosts(&selections, "t", now, half_life).len(), 2);
+    assert!(boosts(&selections, "terminal", now, half_life).is_empty());
+
+    // Old selections are forgotten
+    assert!(forgotten(&selections, now, half_life).is_empty());
+    assert_eq!(forgotten(&selections, now + 60 * DAY, half_life).len(), 2);
+
+    // The store is capped
+    let many: Vec<Selection> = (0..MAX_SELECTIONS + 10)
+        .map(|i| selection(&format!("q{}", i), "kitty", 1, now - i as u64))
+        .collect();
+    let dropped = forgotten(&many, now, half_life);
+    assert_eq!(dropped.len(), 10);
+    assert!(dropped
+        .iter()
+        .all(|s| s.last <= now - MAX_SELECTIONS as u64));
+}
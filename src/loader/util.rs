@@ -1,12 +1,15 @@
+use nix::unistd::getuid;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::env;
-use std::fs::File;
+use std::fs::{self, DirBuilder, File, Permissions};
 use std::io::{self, BufRead, BufReader, Read};
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::{Arc, RwLock};
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Clone)]
 pub struct RawLauncher {
     pub name: Option<String>,
     pub alias: Option<String>,
@@ -32,19 +35,57 @@ pub struct RawLauncher {
     pub args: serde_json::Value,
 }
 
+impl RawLauncher {
+    /// Volatile launchers show live data and are refreshed every time the window opens
+    pub fn is_volatile(&self) -> bool {
+        matches!(
+            self.r#type.as_str(),
+            "clipboard-execution" | "process" | "audio_sink" | "teams_event"
+        )
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct AppData {
     pub icon: String,
     pub icon_class: Option<String>,
     pub exec: String,
     pub search_string: String,
+    /// Searched with less weight than the search string
+    #[serde(default)]
+    pub generic_name: Option<String>,
+    #[serde(default)]
+    pub comment: Option<String>,
     pub tag_start: Option<String>,
     pub tag_end: Option<String>,
     pub desktop_file: Option<PathBuf>,
+    /// Directory the app is started in
+    #[serde(default)]
+    pub working_dir: Option<PathBuf>,
+    /// Additional actions like "New Private Window"
+    #[serde(default)]
+    pub actions: Vec<AppAction>,
+    /// Whether the app is handed an activation token on launch
+    #[serde(default)]
+    pub startup_notify: bool,
+    /// Whether the app is started through `org.freedesktop.Application`
+    #[serde(default)]
+    pub dbus_activatable: bool,
     #[serde(default)]
     pub priority: f32,
 }
 
+/// A `[Desktop Action]` of an app
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct AppAction {
+    /// Identifier from the `Actions` key
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    pub exec: String,
+    pub icon: Option<String>,
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct SherlockFlags {
     pub config: Option<PathBuf>,
@@ -52,6 +93,8 @@ pub struct SherlockFlags {
     pub style: Option<PathBuf>,
     pub ignore: Option<PathBuf>,
     pub alias: Option<PathBuf>,
+    pub socket: Option<PathBuf>,
+    pub lock: Option<PathBuf>,
     pub display_raw: bool,
     pub center_raw: bool,
     pub cache: Option<PathBuf>,
@@ -85,14 +128,18 @@ pub enum SherlockErrorType {
     FlagLoadError,
     RegexError(String),
     CommandExecutionError(String),
+    CommandExitError(String),
     ClipboardError,
     DBusConnectionError,
     DBusMessageSendError(String),
     DBusMessageConstructError(String),
     HttpRequestError(String),
+    SocketBindError(String),
     SocketRemoveError(String),
     SocketConnectError(String),
     SoecktWriteError(String),
+    SocketReadError(String),
+    DatabaseError(PathBuf),
 }
 
 impl SherlockErrorType {
@@ -102,6 +149,10 @@ impl SherlockErrorType {
                 "EnvVarNotFoundError".to_string(),
                 format!("Failed to unpack environment variable \"{}\"", var),
             ),
+            SherlockErrorType::SocketBindError(socket) => (
+                "SocketBindError".to_string(),
+                format!("Failed to bind socket at location \"{}\"", socket),
+            ),
             SherlockErrorType::SocketRemoveError(socket) => (
                 "SocketRemoveError".to_string(),
                 format!("Failed to close socket at location \"{}\"", socket),
@@ -117,6 +168,13 @@ impl SherlockErrorType {
                     socket
                 ),
             ),
+            SherlockErrorType::SocketReadError(socket) => (
+                "SocketReadError".to_string(),
+                format!(
+                    "Failed to read reply from socket at location \"{}\"",
+                    socket
+                ),
+            ),
             SherlockErrorType::FileExistError(file) => (
                 "FileExistError".to_string(),
                 format!("File \"{}\" does not exist", file.to_string_lossy()),
@@ -172,6 +230,10 @@ impl SherlockErrorType {
                 format!("CommandExecutionError"),
                 format!("Failed to execute command \"{}\"", cmd),
             ),
+            SherlockErrorType::CommandExitError(cmd) => (
+                format!("CommandExitError"),
+                format!("Command \"{}\" exited right after its launch", cmd),
+            ),
             SherlockErrorType::ClipboardError => (
                 format!("ClipboardError"),
                 format!("Failed to get system clipboard"),
@@ -192,6 +254,10 @@ impl SherlockErrorType {
                 format!("HttpRequestError"),
                 format!("Failed to get requested source \"{}\"", cmd),
             ),
+            SherlockErrorType::DatabaseError(file) => (
+                format!("DatabaseError"),
+                format!("Failed to access database \"{}\"", file.to_string_lossy()),
+            ),
         }
     }
 }
@@ -208,6 +274,8 @@ pub struct SherlockConfig {
     #[serde(default)]
     pub units: ConfigUnits,
     #[serde(default)]
+    pub calc: ConfigCalc,
+    #[serde(default)]
     pub debug: ConfigDebug,
     #[serde(default)]
     pub appearance: ConfigAppearance,
@@ -225,6 +293,7 @@ impl SherlockConfig {
         SherlockConfig {
             default_apps: ConfigDefaultApps::default(),
             units: ConfigUnits::default(),
+            calc: ConfigCalc::default(),
             debug: ConfigDebug::default(),
             appearance: ConfigAppearance::default(),
             behavior: ConfigBehavior::default(),
@@ -235,6 +304,37 @@ impl SherlockConfig {
     }
 }
 
+/// Holds the active configuration. In daemon mode it gets replaced whenever
+/// the config file changes, so the returned handle should not be stored.
+pub struct SharedConfig {
+    inner: RwLock<Option<Arc<SherlockConfig>>>,
+}
+impl SharedConfig {
+    pub const fn new() -> Self {
+        Self {
+            inner: RwLock::new(None),
+        }
+    }
+    pub fn get(&self) -> Option<Arc<SherlockConfig>> {
+        self.inner.read().ok().and_then(|c| c.clone())
+    }
+    /// Sets the initial configuration. Fails if it was already set.
+    pub fn set(&self, config: SherlockConfig) -> Result<(), SherlockConfig> {
+        match self.inner.write() {
+            Ok(mut inner) if inner.is_none() => {
+                *inner = Some(Arc::new(config));
+                Ok(())
+            }
+            _ => Err(config),
+        }
+    }
+    pub fn replace(&self, config: SherlockConfig) {
+        if let Ok(mut inner) = self.inner.write() {
+            *inner = Some(Arc::new(config));
+        }
+    }
+}
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct ConfigDefaultApps {
     #[serde(default = "default_teams")]
@@ -264,7 +364,7 @@ pub struct ConfigUnits {
     #[serde(default = "default_temperatures")]
     pub temperatures: String,
     #[serde(default = "default_currency")]
-    pub _currency: String,
+    pub currency: String,
 }
 impl Default for ConfigUnits {
     fn default() -> Self {
@@ -273,7 +373,26 @@ impl Default for ConfigUnits {
             weights: default_weights(),
             volumes: default_volumes(),
             temperatures: default_temperatures(),
-            _currency: default_currency(),
+            currency: default_currency(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ConfigCalc {
+    #[serde(default = "default_calc_history")]
+    pub history: usize,
+    #[serde(default)]
+    pub constants: HashMap<String, f64>,
+    #[serde(default = "default_calc_precision")]
+    pub precision: usize,
+}
+impl Default for ConfigCalc {
+    fn default() -> Self {
+        Self {
+            history: default_calc_history(),
+            constants: HashMap::new(),
+            precision: default_calc_precision(),
         }
     }
 }
@@ -315,6 +434,8 @@ pub struct ConfigAppearance {
     pub use_base_css: bool,
     #[serde(default = "default_true")]
     pub status_bar: bool,
+    #[serde(default = "default_match_highlight")]
+    pub match_highlight: String,
 }
 impl Default for ConfigAppearance {
     fn default() -> Self {
@@ -327,6 +448,7 @@ impl Default for ConfigAppearance {
             search_icon: false,
             use_base_css: true,
             status_bar: true,
+            match_highlight: default_match_highlight(),
         }
     }
 }
@@ -345,6 +467,12 @@ pub struct ConfigBehavior {
     pub field: Option<String>,
     pub global_prefix: Option<String>,
     pub global_flags: Option<String>,
+    /// Days after which a launch counts half as much for the ranking
+    #[serde(default = "default_frecency_half_life")]
+    pub frecency_half_life: f32,
+    /// Starts every launched app in its own systemd scope
+    #[serde(default)]
+    pub systemd_scopes: bool,
 }
 impl Default for ConfigBehavior {
     fn default() -> Self {
@@ -356,6 +484,8 @@ impl Default for ConfigBehavior {
             field: None,
             global_prefix: None,
             global_flags: None,
+            frecency_half_life: default_frecency_half_life(),
+            systemd_scopes: false,
         }
     }
 }
@@ -372,6 +502,10 @@ pub struct ConfigFiles {
     pub alias: PathBuf,
     #[serde(default = "default_ignore")]
     pub ignore: PathBuf,
+    #[serde(default = "default_socket")]
+    pub socket: PathBuf,
+    #[serde(default = "default_lock")]
+    pub lock: PathBuf,
 }
 impl Default for ConfigFiles {
     fn default() -> Self {
@@ -381,6 +515,8 @@ impl Default for ConfigFiles {
             fallback: default_fallback(),
             alias: default_alias(),
             ignore: default_ignore(),
+            socket: default_socket(),
+            lock: default_lock(),
         }
     }
 }
@@ -425,8 +561,47 @@ pub fn home_dir() -> Result<PathBuf, SherlockError> {
         })
         .map(|s| PathBuf::from(s))
 }
-pub fn parse_priority(priority: f32, count: f32, decimals: i32) -> f32 {
-    priority + 1.0 - count * 10f32.powi(-decimals)
+/// Directory holding the daemon socket and the lock file. Uses `$XDG_RUNTIME_DIR/sherlock`
+/// and falls back to `/tmp/sherlock-$UID`.
+pub fn runtime_dir() -> PathBuf {
+    match env::var("XDG_RUNTIME_DIR") {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir).join("sherlock"),
+        _ => PathBuf::from(format!("/tmp/sherlock-{}", getuid())),
+    }
+}
+
+/// Creates the parent directory of a runtime file. The default runtime directory is
+/// restricted to the current user, user-specified locations are only created.
+pub fn prepare_runtime_dir(file: &Path) -> Result<(), SherlockError> {
+    let dir = match file.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => return Ok(()),
+    };
+    let dir_error = |e: String| SherlockError {
+        error: SherlockErrorType::DirCreateError(dir.to_string_lossy().to_string()),
+        traceback: e,
+    };
+    if dir != runtime_dir() {
+        return fs::create_dir_all(dir).map_err(|e| dir_error(e.to_string()));
+    }
+
+    DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(dir)
+        .map_err(|e| dir_error(e.to_string()))?;
+    let metadata = fs::metadata(dir).map_err(|e| dir_error(e.to_string()))?;
+    if metadata.uid() != getuid().as_raw() {
+        return Err(dir_error(format!(
+            "The directory is owned by another user (uid {}).",
+            metadata.uid()
+        )));
+    }
+    if metadata.permissions().mode() & 0o077 != 0 {
+        fs::set_permissions(dir, Permissions::from_mode(0o700))
+            .map_err(|e| dir_error(e.to_string()))?;
+    }
+    Ok(())
 }
 
 pub fn expand_path(path: &Path, home: &Path) -> PathBuf {
@@ -466,10 +641,19 @@ pub fn default_temperatures() -> String {
 pub fn default_currency() -> String {
     String::from("eur")
 }
+pub fn default_calc_history() -> usize {
+    20
+}
+pub fn default_calc_precision() -> usize {
+    10
+}
 
 pub fn default_cache() -> PathBuf {
     PathBuf::from("~/.cache/sherlock/sherlock_desktop_cache.json")
 }
+pub fn default_frecency_half_life() -> f32 {
+    7.0
+}
 pub fn default_config() -> PathBuf {
     PathBuf::from("~/.config/sherlock/config.toml")
 }
@@ -485,6 +669,15 @@ pub fn default_alias() -> PathBuf {
 pub fn default_ignore() -> PathBuf {
     PathBuf::from("~/.config/sherlock/sherlockignore")
 }
+pub fn default_match_highlight() -> String {
+    String::from("weight=\"bold\"")
+}
+pub fn default_socket() -> PathBuf {
+    runtime_dir().join("sherlock.socket")
+}
+pub fn default_lock() -> PathBuf {
+    runtime_dir().join("sherlock.lock")
+}
 
 pub fn default_true() -> bool {
     true
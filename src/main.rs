@@ -5,7 +5,8 @@ use gtk4::prelude::GtkApplicationExt;
 use gtk4::Application;
 use loader::pipe_loader::deserialize_pipe;
 use loader::util::SherlockErrorType;
-use std::sync::OnceLock;
+use std::cell::RefCell;
+use std::rc::Rc;
 use std::time::Instant;
 use std::{env, process, thread};
 
@@ -19,17 +20,14 @@ mod loader;
 mod ui;
 
 // IMPORTS
-use application::lock;
+use application::{lock, util::handle_daemon_command};
 use daemon::daemon::SherlockDaemon;
 use loader::{
-    util::{SherlockConfig, SherlockError},
+    util::{SharedConfig, SherlockError},
     Loader,
 };
 
-const SOCKET_PATH: &str = "/tmp/sherlock_daemon.socket";
-const LOCK_FILE: &str = "/tmp/sherlock.lock";
-
-static CONFIG: OnceLock<SherlockConfig> = OnceLock::new();
+static CONFIG: SharedConfig = SharedConfig::new();
 
 #[tokio::main]
 async fn main() {
@@ -37,12 +35,6 @@ async fn main() {
     let mut non_breaking: Vec<SherlockError> = Vec::new();
     let mut startup_errors: Vec<SherlockError> = Vec::new();
 
-    // Check for '.lock'-file to only start a single instance
-    let _lock = lock::ensure_single_instance(LOCK_FILE).unwrap_or_else(|e| {
-        eprintln!("{}", e);
-        process::exit(1);
-    });
-
     // Setup flags
     let sherlock_flags = Loader::load_flags()
         .map_err(|e| startup_errors.push(e))
@@ -71,6 +63,18 @@ async fn main() {
         })
         .ok();
 
+    // Act as a client for an already running daemon
+    let args: Vec<String> = env::args().collect();
+    if args.get(1).map(String::as_str) == Some("msg") {
+        process::exit(daemon::client::run(&args[2..]));
+    }
+
+    // Check for '.lock'-file to only start a single instance
+    let _lock = lock::ensure_single_instance(&app_config.files.lock).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(1);
+    });
+
     Loader::load_resources()
         .map_err(|e| startup_errors.push(e))
         .ok();
@@ -130,6 +134,7 @@ async fn main() {
         // Either show user-specified content or show normal search
         let pipe = Loader::load_pipe_args();
         let search_stack = if pipe.is_empty() {
+            let launchers = Rc::new(RefCell::new(launchers.clone()));
             ui::search::search(&launchers, &window, &current_stack_page)
         } else {
             if sherlock_flags.display_raw {
@@ -178,17 +183,37 @@ async fn main() {
 
                     // Create async pipeline
                     let (sender, receiver) = async_channel::bounded(1);
+                    let dbus_sender = sender.clone();
+                    let watch_sender = sender.clone();
+                    let daemon = SherlockDaemon::new().unwrap_or_else(|e| {
+                        let (_, message) = e.error.get_message();
+                        eprintln!("{}: {}", message, e.traceback);
+                        process::exit(1);
+                    });
+                    thread::spawn(move || daemon.listen(sender));
+                    // Reload when the config files change
+                    thread::spawn(move || daemon::watcher::watch(watch_sender));
+
+                    // Export the same commands on the session bus
                     thread::spawn(move || {
                         async_std::task::block_on(async {
-                            let _daemon = SherlockDaemon::new(sender).await;
+                            if let Err(e) = daemon::dbus::serve(dbus_sender).await {
+                                eprintln!("Failed to register on the session bus: {}", e);
+                            }
                         });
                     });
                     // Handle receiving using pipline
+                    let app = app.clone();
                     MainContext::default().spawn_local(async move {
-                        while let Ok(_msg) = receiver.recv().await {
-                            let _ = gtk4::prelude::WidgetExt::activate_action(
-                                &window, "win.open", None,
+                        while let Ok(request) = receiver.recv().await {
+                            let reply = handle_daemon_command(
+                                &app,
+                                &window,
+                                &stack,
+                                &current_stack_page,
+                                request.command,
                             );
+                            let _ = request.reply.send(reply).await;
                         }
                     });
                 }
@@ -200,4 +225,7 @@ async fn main() {
         }
     });
     application.run();
+    if let Some(c) = CONFIG.get() {
+        let _ = std::fs::remove_file(&c.files.socket);
+    }
 }
@@ -15,8 +15,10 @@ use std::rc::Rc;
 use super::tiles::util::AsyncLauncherTile;
 use super::util::*;
 use crate::actions::execute_from_attrs;
+use crate::daemon::dbus;
 use crate::g_subclasses::sherlock_row::SherlockRow;
 use crate::launcher::{construct_tiles, Launcher, ResultItem};
+use crate::loader::query_memory::selection_boosts;
 use crate::CONFIG;
 
 #[allow(dead_code)]
@@ -31,17 +33,18 @@ struct SearchUI {
 }
 
 pub fn search(
-    launchers: &Vec<Launcher>,
+    launchers: &Rc<RefCell<Vec<Launcher>>>,
     window: &ApplicationWindow,
     stack_page_ref: &Rc<RefCell<String>>,
 ) -> HVBox {
     // Initialize the view to show all apps
-    let (mode, modes, stack_page, ui, results) = construct_window(&launchers);
+    let (mode, modes, stack_page, ui, results) = construct_window(&launchers.borrow());
     ui.result_viewport
         .set_policy(gtk4::PolicyType::Automatic, gtk4::PolicyType::Automatic);
 
     let search_bar_clone = ui.search_bar.clone();
     let search_bar_clone2 = ui.search_bar.clone();
+    let search_bar_clone3 = ui.search_bar.clone();
     let modes_clone = modes.clone();
     let mode_clone = Rc::clone(&mode);
 
@@ -79,6 +82,7 @@ pub fn search(
             let parameter = parameter.and_then(|p| p.get::<String>());
 
             if let (Some(mut state), Some(mut parameter)) = (state, parameter) {
+                let previous = state.clone();
                 match parameter.as_str() {
                     "search" => {
                         ui.search_icon_holder.set_css_classes(&["back"]);
@@ -109,6 +113,9 @@ pub fn search(
                             search_bar_clone.set_text("");
                             glib::ControlFlow::Break
                         });
+                        if state != previous {
+                            dbus::emit_mode_changed(state.trim());
+                        }
                         action.set_state(&state.to_variant());
                     }
                 }
@@ -141,7 +148,26 @@ pub fn search(
             });
         })
         .build();
-    window.add_action_entries([mode_action, action_clear_win, action_spinner]);
+    let action_set_search = ActionEntry::builder("set-search")
+        .parameter_type(Some(&String::static_variant_type()))
+        .activate(move |_: &ApplicationWindow, _, parameter| {
+            if let Some(text) = parameter.and_then(|p| p.get::<String>()) {
+                let search_bar_clone = search_bar_clone3.clone();
+                // Scheduled after the rebuild triggered by 'switch-mode'
+                glib::idle_add_local(move || {
+                    search_bar_clone.set_text(&text);
+                    search_bar_clone.set_position(-1);
+                    glib::ControlFlow::Break
+                });
+            }
+        })
+        .build();
+    window.add_action_entries([
+        mode_action,
+        action_clear_win,
+        action_spinner,
+        action_set_search,
+    ]);
 
     return stack_page;
 }
@@ -316,7 +342,7 @@ fn change_event(
     search_bar: &Entry,
     modes: HashMap<String, Option<String>>,
     mode: &Rc<RefCell<String>>,
-    launchers: &Vec<Launcher>,
+    launchers: &Rc<RefCell<Vec<Launcher>>>,
     results: &Rc<ListBox>,
     custom_binds: &ConfKeys,
 ) {
@@ -331,7 +357,7 @@ fn change_event(
     async_calc(
         &cancel_flag,
         &current_task,
-        &launchers,
+        &launchers.borrow(),
         &mode,
         String::new(),
         &results,
@@ -340,7 +366,7 @@ fn change_event(
     );
 
     search_bar.connect_changed({
-        let launchers_clone = launchers.clone();
+        let launchers_clone = Rc::clone(launchers);
         let mode_clone = Rc::clone(mode);
         let results_clone = Rc::clone(results);
 
@@ -364,7 +390,7 @@ fn change_event(
             async_calc(
                 &cancel_flag,
                 &current_task,
-                &launchers_clone,
+                &launchers_clone.borrow(),
                 &mode_clone,
                 current_text,
                 &results_clone,
@@ -555,7 +581,19 @@ pub fn populate(
         launcher_tiles.extend(widgets);
     }
 
-    launcher_tiles.sort_by(|a, b| a.priority.partial_cmp(&b.priority).unwrap());
+    // Lift the results picked for this query before
+    let boosts = selection_boosts(keyword);
+    if !boosts.is_empty() {
+        launcher_tiles
+            .iter_mut()
+            .for_each(|tile| tile.apply_selection_boost(&boosts));
+    }
+
+    launcher_tiles.sort_by(|a, b| {
+        a.rank()
+            .partial_cmp(&b.rank())
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
 
     if let Some(c) = CONFIG.get() {
         let mut shortcut_index = 1;
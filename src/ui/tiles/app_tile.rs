@@ -4,9 +4,9 @@ use std::collections::HashMap;
 use crate::actions::{execute_from_attrs, get_attrs_map};
 use crate::g_subclasses::sherlock_row::SherlockRow;
 use crate::launcher::{Launcher, ResultItem};
-use crate::loader::util::AppData;
+use crate::loader::util::{AppAction, AppData};
 
-use super::util::{SherlockSearch, TileBuilder};
+use super::util::{action_search_score, match_markup, SherlockSearch, TileBuilder};
 use super::Tile;
 
 impl Tile {
@@ -18,43 +18,16 @@ impl Tile {
         let mut results: Vec<ResultItem> = Default::default();
 
         for (key, value) in commands.into_iter() {
-            if value
-                .search_string
-                .to_lowercase()
-                .fuzzy_match(&keyword.to_lowercase())
-            {
-                let builder = TileBuilder::new("/dev/skxxtz/sherlock/ui/tile.ui");
-                builder.object.set_spawn_focus(launcher.spawn_focus);
-                builder.object.set_shortcut(launcher.shortcut);
-
-                let tile_name = key.replace("{keyword}", keyword);
-                builder.display_tag_start(&value.tag_start, keyword);
-                builder.display_tag_end(&value.tag_end, keyword);
-
+            let tile_name = key.replace("{keyword}", keyword);
+            if let Some(relevance) = value.search_score(keyword) {
+                let builder = app_row(launcher, keyword, &tile_name, value, None);
                 if let Some(name) = &launcher.name {
                     builder.category.set_text(name);
                 } else {
                     builder.category.set_visible(false);
                 }
-
-                // Icon stuff
                 builder.icon.set_icon_name(Some(&value.icon));
-                value
-                    .icon_class
-                    .as_ref()
-                    .map(|c| builder.icon.add_css_class(c));
-                builder.title.set_markup(&tile_name);
-
-                let attrs =
-                    get_attrs_map(vec![("method", &launcher.method), ("exec", &value.exec)]);
-
-                builder
-                    .object
-                    .connect("row-should-activate", false, move |row| {
-                        let row = row.first().map(|f| f.get::<SherlockRow>().ok())??;
-                        execute_from_attrs(&row, &attrs);
-                        None
-                    });
+                builder.title.set_markup(&match_markup(&tile_name, keyword));
 
                 let shortcut_holder = match launcher.shortcut {
                     true => builder.shortcut_holder,
@@ -62,11 +35,97 @@ impl Tile {
                 };
                 results.push(ResultItem {
                     priority: value.priority,
+                    relevance,
+                    exec: Some(value.exec.clone()),
                     row_item: builder.object,
                     shortcut_holder,
                 });
             }
+
+            // Desktop actions are listed below the app they belong to
+            for action in value.actions.iter() {
+                if let Some(relevance) = action_search_score(value, action, keyword) {
+                    let builder = app_row(launcher, keyword, &tile_name, value, Some(action));
+                    builder.category.set_text(&tile_name);
+                    builder
+                        .icon
+                        .set_icon_name(Some(action.icon.as_deref().unwrap_or(&value.icon)));
+                    builder
+                        .title
+                        .set_markup(&match_markup(&action.name, keyword));
+
+                    let shortcut_holder = match launcher.shortcut {
+                        true => builder.shortcut_holder,
+                        _ => None,
+                    };
+                    results.push(ResultItem {
+                        priority: value.priority,
+                        relevance,
+                        exec: Some(action.exec.clone()),
+                        row_item: builder.object,
+                        shortcut_holder,
+                    });
+                }
+            }
         }
         return results;
     }
 }
+
+/// Row launching the app or one of its actions on activation, with the tags
+/// and icon style of `value`
+fn app_row(
+    launcher: &Launcher,
+    keyword: &str,
+    name: &str,
+    value: &AppData,
+    action: Option<&AppAction>,
+) -> TileBuilder {
+    let builder = TileBuilder::new("/dev/skxxtz/sherlock/ui/tile.ui");
+    builder.object.set_spawn_focus(launcher.spawn_focus);
+    builder.object.set_shortcut(launcher.shortcut);
+    builder.display_tag_start(&value.tag_start, keyword);
+    builder.display_tag_end(&value.tag_end, keyword);
+    value
+        .icon_class
+        .as_ref()
+        .map(|c| builder.icon.add_css_class(c));
+
+    let exec = action.map_or(&value.exec, |a| &a.exec);
+    let mut attrs = get_attrs_map(vec![
+        ("method", &launcher.method),
+        ("exec", exec),
+        ("query", keyword),
+        ("name", name),
+        ("icon", &value.icon),
+    ]);
+    if let Some(file) = &value.desktop_file {
+        attrs.insert(
+            String::from("desktop_file"),
+            file.to_string_lossy().to_string(),
+        );
+    }
+    if let Some(action) = action {
+        attrs.insert(String::from("action"), action.id.clone());
+    }
+    if value.startup_notify {
+        attrs.insert(String::from("startup_notify"), String::from("true"));
+    }
+    if value.dbus_activatable {
+        attrs.insert(String::from("dbus_activatable"), String::from("true"));
+    }
+    if let Some(dir) = &value.working_dir {
+        attrs.insert(
+            String::from("working_dir"),
+            dir.to_string_lossy().to_string(),
+        );
+    }
+    builder
+        .object
+        .connect("row-should-activate", false, move |row| {
+            let row = row.first().map(|f| f.get::<SherlockRow>().ok())??;
+            execute_from_attrs(&row, &attrs);
+            None
+        });
+    builder
+}
@@ -37,6 +37,8 @@ impl Tile {
         };
         let result_item = ResultItem {
             priority: launcher.priority as f32,
+            relevance: 0.0,
+            exec: None,
             row_item: builder.object,
             shortcut_holder,
         };
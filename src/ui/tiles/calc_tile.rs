@@ -1,14 +1,21 @@
+use chrono::Datelike;
 use gio::glib::object::ObjectExt;
+use gio::glib::{DateTime, TimeZone};
 use gtk4::prelude::WidgetExt;
-use meval::eval_str;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use super::util::TileBuilder;
 use super::Tile;
 use crate::{
     actions::{execute_from_attrs, get_attrs_map},
     g_subclasses::sherlock_row::SherlockRow,
-    launcher::{calc_launcher::Calculator, Launcher, ResultItem},
+    launcher::{
+        calc_launcher::Calculator,
+        datetime::{self, TimeResult},
+        precise::{precision, Number},
+        programmer, Launcher, ResultItem,
+    },
+    loader::calc_history::{recent_calculations, record_calculation},
 };
 
 impl Tile {
@@ -17,39 +24,57 @@ impl Tile {
         calc_launcher: &Calculator,
         keyword: &str,
     ) -> Vec<ResultItem> {
-        let capabilities: HashSet<&str> = match &calc_launcher.capabilities {
-            Some(c) => c.iter().map(|s| s.as_str()).collect(),
-            _ => HashSet::from(["calc.math", "calc.units"]),
-        };
-        let mut result: Option<String> = None;
+        let capabilities = capabilities(calc_launcher);
+        if keyword.trim().is_empty() {
+            return vec![];
+        }
 
-        if capabilities.contains("calc.math") {
-            let trimmed_keyword = keyword.trim();
-            if let Ok(r) = eval_str(trimmed_keyword) {
-                let r = r.to_string();
-                if &r != trimmed_keyword {
-                    result = Some(format!("= {}", r));
-                }
+        if capabilities.contains("calc.programmer") {
+            if let Some(res) = programmer::evaluate(keyword) {
+                // One row per base, each copying its own value
+                return res
+                    .in_bases()
+                    .into_iter()
+                    .map(|(base, value)| {
+                        let equation = format!("{}  ·  {}", keyword, base.name());
+                        let result = format!("= {}", value);
+                        Self::calc_row(launcher, &equation, &result, value_attrs(&value))
+                    })
+                    .collect();
             }
         }
 
-        if (capabilities.contains("calc.lengths") || capabilities.contains("calc.units"))
-            && result.is_none()
-        {
-            result = calc_launcher.measurement(&keyword, "lengths")
+        if capabilities.contains("calc.time") {
+            if let Some(res) = datetime::evaluate(keyword) {
+                return Self::time_rows(launcher, keyword, &res);
+            }
         }
 
-        if (capabilities.contains("calc.weights") || capabilities.contains("calc.units"))
-            && result.is_none()
-        {
-            result = calc_launcher.measurement(&keyword, "weights")
+        if capabilities.contains("calc.math") {
+            if let Some(res) = calc_launcher.math(keyword) {
+                // Remembered as `ans` and in the history once activated.
+                // Assignments clear the search bar for the next expression.
+                let mut attrs = get_attrs_map(vec![
+                    ("expression", keyword.trim()),
+                    ("ans", &res.value.raw()),
+                ]);
+                if let Some(variable) = &res.assignment {
+                    attrs.insert(String::from("variable"), variable.clone());
+                    attrs.insert(String::from("method"), String::from("set_search"));
+                    attrs.insert(String::from("keyword"), String::new());
+                }
+                let precision = precision();
+                let result = format!("= {}", res.value.format(precision));
+                // Rounded fractions are also shown exactly
+                let equation = match res.value.fraction(precision) {
+                    Some(fraction) => format!("{}  ·  {}", keyword, fraction),
+                    None => keyword.to_string(),
+                };
+                return vec![Self::calc_row(launcher, &equation, &result, attrs)];
+            }
         }
 
-        if (capabilities.contains("calc.volumes") || capabilities.contains("calc.units"))
-            && result.is_none()
-        {
-            result = calc_launcher.measurement(&keyword, "volumes")
-        }
+        let mut result = calc_launcher.measurement(&keyword, &capabilities);
 
         if (capabilities.contains("calc.temperatures") || capabilities.contains("calc.units"))
             && result.is_none()
@@ -57,39 +82,152 @@ impl Tile {
             result = calc_launcher.temperature(&keyword)
         }
 
-        if let Some(r) = result {
-            let builder = TileBuilder::new("/dev/skxxtz/sherlock/ui/calc_tile.ui");
-            builder.object.add_css_class("calc-tile");
-            builder.object.set_spawn_focus(launcher.spawn_focus);
-            builder.object.set_shortcut(launcher.shortcut);
-
-            builder.equation_holder.set_text(&keyword);
-            builder.result_holder.set_text(&r);
-
-            // Add action capabilities
-            let attrs = get_attrs_map(vec![("method", &launcher.method), ("result", &r)]);
-            builder
-                .object
-                .connect("row-should-activate", false, move |row| {
-                    let row = row.first().map(|f| f.get::<SherlockRow>().ok())??;
-                    execute_from_attrs(&row, &attrs);
-                    None
-                });
-
-            let shortcut_holder = match launcher.shortcut {
-                true => builder.shortcut_holder,
-                _ => None,
-            };
-
-            let res = ResultItem {
-                priority: launcher.priority as f32,
-                row_item: builder.object,
-                shortcut_holder,
-            };
-
-            vec![res]
-        } else {
+        // Shown next to the equation, e.g. the age of exchange rates
+        let mut note: Option<String> = None;
+        if capabilities.contains("calc.currencies") && result.is_none() {
+            if let Some((r, age)) = calc_launcher.currency(&keyword) {
+                result = Some(r);
+                note = Some(age);
+            }
+        }
+
+        let Some(r) = result else {
+            return vec![];
+        };
+        let equation = match &note {
+            Some(note) => format!("{}  ·  {}", keyword, note),
+            None => keyword.to_string(),
+        };
+        vec![Self::calc_row(launcher, &equation, &r, HashMap::new())]
+    }
+
+    /// The calculator history, shown once the mode of the calculator is opened.
+    /// Activating an entry puts it back into the search bar.
+    pub fn calc_history_tile(launcher: &Launcher, calc_launcher: &Calculator) -> Vec<ResultItem> {
+        if !capabilities(calc_launcher).contains("calc.math") {
             return vec![];
         }
+        recent_calculations()
+            .into_iter()
+            .map(|calculation| {
+                let result = format!("= {}", calculation.result);
+                let attrs = get_attrs_map(vec![
+                    ("method", "set_search"),
+                    ("keyword", &calculation.expression),
+                ]);
+                Self::calc_row(launcher, &calculation.expression, &result, attrs)
+            })
+            .collect()
+    }
+
+    /// Rows for a date or time result. Points in time can be copied in the
+    /// format of the locale, as ISO 8601 and as unix time.
+    pub fn time_rows(launcher: &Launcher, keyword: &str, result: &TimeResult) -> Vec<ResultItem> {
+        let mut formats: Vec<(&str, String)> = Vec::new();
+        match result {
+            TimeResult::Moment(time, zone) => {
+                let tz = match zone {
+                    Some(zone) => TimeZone::new(Some(zone.as_str())),
+                    None => TimeZone::local(),
+                };
+                let locale = DateTime::from_unix_utc(time.timestamp())
+                    .and_then(|t| t.to_timezone(&tz))
+                    .and_then(|t| t.format("%c"));
+                if let Ok(locale) = locale {
+                    formats.push((zone.as_deref().unwrap_or("local time"), locale.to_string()));
+                }
+                formats.push(("ISO 8601", time.to_rfc3339()));
+                formats.push(("unix time", time.timestamp().to_string()));
+            }
+            TimeResult::Date(date) => {
+                let locale = DateTime::new(
+                    &TimeZone::local(),
+                    date.year(),
+                    date.month() as i32,
+                    date.day() as i32,
+                    0,
+                    0,
+                    0.0,
+                )
+                .and_then(|d| d.format("%A, %x"));
+                if let Ok(locale) = locale {
+                    formats.push(("date", locale.to_string()));
+                }
+                formats.push(("ISO 8601", date.format("%Y-%m-%d").to_string()));
+            }
+            TimeResult::Span(span) => formats.push(("duration", span.clone())),
+            TimeResult::Timestamp(timestamp) => formats.push(("unix time", timestamp.to_string())),
+        }
+        formats
+            .into_iter()
+            .map(|(label, value)| {
+                let equation = format!("{}  ·  {}", keyword, label);
+                let result = format!("= {}", value);
+                Self::calc_row(launcher, &equation, &result, value_attrs(&value))
+            })
+            .collect()
+    }
+
+    /// A calculator row. `attrs` extend or override the launcher method and
+    /// the result handed to it.
+    fn calc_row(
+        launcher: &Launcher,
+        equation: &str,
+        result: &str,
+        attrs: HashMap<String, String>,
+    ) -> ResultItem {
+        let builder = TileBuilder::new("/dev/skxxtz/sherlock/ui/calc_tile.ui");
+        builder.object.add_css_class("calc-tile");
+        builder.object.set_spawn_focus(launcher.spawn_focus);
+        builder.object.set_shortcut(launcher.shortcut);
+
+        builder.equation_holder.set_text(equation);
+        builder.result_holder.set_text(result);
+
+        // Add action capabilities
+        let mut row_attrs = get_attrs_map(vec![("method", &launcher.method), ("result", result)]);
+        row_attrs.extend(attrs);
+        let attrs = row_attrs;
+        builder
+            .object
+            .connect("row-should-activate", false, move |row| {
+                let row = row.first().map(|f| f.get::<SherlockRow>().ok())??;
+                if let Some(ans) = attrs.get("ans").and_then(|a| Number::parse(a)) {
+                    Calculator::remember(ans, attrs.get("variable").map(|v| v.as_str()));
+                }
+                if let (Some(expression), Some(result)) =
+                    (attrs.get("expression"), attrs.get("result"))
+                {
+                    let _ = record_calculation(expression, result.trim_start_matches("= "));
+                }
+                execute_from_attrs(&row, &attrs);
+                None
+            });
+
+        let shortcut_holder = match launcher.shortcut {
+            true => builder.shortcut_holder,
+            _ => None,
+        };
+
+        ResultItem {
+            priority: launcher.priority as f32,
+            relevance: 0.0,
+            exec: None,
+            row_item: builder.object,
+            shortcut_holder,
+        }
+    }
+}
+
+/// Makes activation hand `value` to the launcher method instead of the whole
+/// result
+fn value_attrs(value: &str) -> HashMap<String, String> {
+    get_attrs_map(vec![("value", value), ("field", "value")])
+}
+
+fn capabilities(calc_launcher: &Calculator) -> HashSet<&str> {
+    match &calc_launcher.capabilities {
+        Some(c) => c.iter().map(|s| s.as_str()).collect(),
+        _ => HashSet::from(["calc.math", "calc.units"]),
     }
 }
@@ -7,6 +7,7 @@ use crate::actions::{execute_from_attrs, get_attrs_map};
 use crate::g_subclasses::sherlock_row::SherlockRow;
 use crate::launcher::calc_launcher::Calculator;
 use crate::launcher::clipboard_launcher::ClipboardLauncher;
+use crate::launcher::datetime;
 use crate::launcher::{Launcher, ResultItem};
 
 use super::util::TileBuilder;
@@ -213,8 +214,18 @@ impl Tile {
                 }
             };
             if !is_valid {
-                // calc capabilities will be checked inside of calc tile
-                results.extend(Tile::calc_tile(launcher, calc, clipboard_content));
+                // Pasted timestamps are shown as dates. Other calc capabilities
+                // will be checked inside of calc tile
+                let timestamp = capabilities
+                    .contains("calc.time")
+                    .then(|| datetime::from_timestamp(clipboard_content))
+                    .flatten();
+                match timestamp {
+                    Some(time) => {
+                        results.extend(Tile::time_rows(launcher, clipboard_content, &time))
+                    }
+                    None => results.extend(Tile::calc_tile(launcher, calc, clipboard_content)),
+                }
             } else {
                 if name.is_empty() {
                     builder.category.set_visible(false);
@@ -245,6 +256,8 @@ impl Tile {
                 };
                 results.push(ResultItem {
                     priority: launcher.priority as f32,
+                    relevance: 0.0,
+                    exec: None,
                     row_item: builder.object,
                     shortcut_holder,
                 });
@@ -63,6 +63,8 @@ impl Tile {
         };
         let res = ResultItem {
             priority: launcher.priority as f32,
+            relevance: 0.0,
+            exec: None,
             row_item: builder.object,
             shortcut_holder,
         };
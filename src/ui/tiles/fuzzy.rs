@@ -0,0 +1,304 @@
+// Weights of the fuzzy matcher
+const SCORE_MATCH: f32 = 1.0;
+const BONUS_CONSECUTIVE: f32 = 2.0;
+const BONUS_BOUNDARY: f32 = 2.0;
+const BONUS_CAMEL_CASE: f32 = 1.5;
+const BONUS_PREFIX: f32 = 1.0;
+const PENALTY_GAP: f32 = 0.1;
+const PENALTY_LEADING: f32 = 0.05;
+const MAX_PENALTY_LEADING: f32 = 1.0;
+const PENALTY_UNMATCHED: f32 = 0.01;
+
+// Matches with typos count at most this much
+const SCORE_TYPO: f32 = 0.5;
+// Lower-weighted fields only count for matches at least this good
+const MIN_SECONDARY_SCORE: f32 = 0.5;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FuzzyMatch {
+    /// Relevance between 0 and 1. A contiguous prefix match scores 1.
+    pub score: f32,
+    /// Char indices of the matched characters within the haystack
+    pub positions: Vec<usize>,
+}
+
+/// Matches the characters of `needle` in order against `haystack`, ignoring
+/// case. Of all possible alignments, the one favouring prefixes, word starts
+/// (acronyms) and consecutive runs is chosen.
+pub fn fuzzy_match(haystack: &str, needle: &str) -> Option<FuzzyMatch> {
+    let hay: Vec<char> = haystack.chars().collect();
+    let lower: Vec<char> = hay.iter().map(|c| fold_case(*c)).collect();
+    let needle: Vec<char> = needle.trim().chars().map(fold_case).collect();
+    let (n, m) = (hay.len(), needle.len());
+    if m == 0 {
+        return Some(FuzzyMatch {
+            score: 0.0,
+            positions: vec![],
+        });
+    }
+    if m > n {
+        return None;
+    }
+    let bonus: Vec<f32> = (0..n).map(|j| boundary_bonus(&hay, j)).collect();
+
+    // scores[i][j]: best score with needle[i] matched at hay[j]
+    let mut scores = vec![vec![f32::NEG_INFINITY; n]; m];
+    let mut previous = vec![vec![0usize; n]; m];
+    for i in 0..m {
+        // Best predecessor for a gapped match, offset by its position
+        let mut best_gap = (f32::NEG_INFINITY, 0usize);
+        for j in i..n {
+            if i > 0 && j >= 2 {
+                let candidate = scores[i - 1][j - 2] + PENALTY_GAP * (j - 2) as f32;
+                if candidate > best_gap.0 {
+                    best_gap = (candidate, j - 2);
+                }
+            }
+            if lower[j] != needle[i] {
+                continue;
+            }
+            let base = SCORE_MATCH + bonus[j];
+            if i == 0 {
+                let prefix = if j == 0 { BONUS_PREFIX } else { 0.0 };
+                let leading = (PENALTY_LEADING * j as f32).min(MAX_PENALTY_LEADING);
+                scores[i][j] = base + prefix - leading;
+                continue;
+            }
+            let consecutive = scores[i - 1][j - 1] + BONUS_CONSECUTIVE;
+            let gapped = best_gap.0 - PENALTY_GAP * (j - 1) as f32;
+            if consecutive >= gapped && consecutive.is_finite() {
+                scores[i][j] = base + consecutive;
+                previous[i][j] = j - 1;
+            } else if gapped.is_finite() {
+                scores[i][j] = base + gapped;
+                previous[i][j] = best_gap.1;
+            }
+        }
+    }
+
+    let (mut end, best) = scores[m - 1]
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| s.is_finite())
+        .max_by(|a, b| a.1.partial_cmp(b.1).unwrap_or(std::cmp::Ordering::Equal))
+        .map(|(j, s)| (j, *s))?;
+
+    let mut positions = vec![0; m];
+    for i in (0..m).rev() {
+        positions[i] = end;
+        end = previous[i][end];
+    }
+
+    let perfect =
+        m as f32 * SCORE_MATCH + (m - 1) as f32 * BONUS_CONSECUTIVE + BONUS_BOUNDARY + BONUS_PREFIX;
+    let score = best - PENALTY_UNMATCHED * (n - m) as f32;
+    Some(FuzzyMatch {
+        score: (score / perfect).clamp(0.0, 1.0),
+        positions,
+    })
+}
+
+/// Scores the query against several weighted fields. Every whitespace separated
+/// token has to match one of the fields, either fuzzily or with a few typos.
+/// Returns the mean of the best weighted token scores.
+pub fn search(fields: &[(&str, f32)], query: &str) -> Option<f32> {
+    let tokens: Vec<&str> = query.split_whitespace().collect();
+    if tokens.is_empty() {
+        return Some(0.0);
+    }
+    let mut total = 0.0;
+    for token in tokens.iter() {
+        total += fields
+            .iter()
+            .filter_map(|(field, weight)| {
+                let score = token_score(field, token)?;
+                (*weight >= 1.0 || score >= MIN_SECONDARY_SCORE).then_some(score * weight)
+            })
+            .max_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))?;
+    }
+    Some(total / tokens.len() as f32)
+}
+
+/// Char indices within `text` matched by any token of the query
+pub fn match_positions(text: &str, query: &str) -> Vec<usize> {
+    let mut positions: Vec<usize> = query
+        .split_whitespace()
+        .filter_map(|token| fuzzy_match(text, token))
+        .flat_map(|m| m.positions)
+        .collect();
+    positions.sort_unstable();
+    positions.dedup();
+    positions
+}
+
+fn token_score(field: &str, token: &str) -> Option<f32> {
+    if let Some(matched) = fuzzy_match(field, token) {
+        return Some(matched.score);
+    }
+    let token: Vec<char> = token.chars().map(fold_case).collect();
+    let max_typos = match token.len() {
+        0..=3 => return None,
+        4..=7 => 1,
+        _ => 2,
+    };
+    field
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .filter_map(|word| {
+            let word: Vec<char> = word.chars().map(fold_case).collect();
+            let distance = prefix_distance(&token, &word);
+            (distance <= max_typos)
+                .then(|| SCORE_TYPO * (1.0 - distance as f32 / token.len() as f32))
+        })
+        .max_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+}
+
+/// Smallest edit distance between `token` and any prefix of `word`, counting
+/// swapped neighbours as a single edit
+fn prefix_distance(token: &[char], word: &[char]) -> usize {
+    let (m, n) = (token.len(), word.len());
+    let mut d = vec![vec![0usize; n + 1]; m + 1];
+    for i in 0..=m {
+        d[i][0] = i;
+    }
+    for j in 0..=n {
+        d[0][j] = j;
+    }
+    for i in 1..=m {
+        for j in 1..=n {
+            let cost = if token[i - 1] == word[j - 1] { 0 } else { 1 };
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && token[i - 1] == word[j - 2] && token[i - 2] == word[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[m].iter().copied().min().unwrap_or(m)
+}
+
+/// Escapes `text` for Pango markup and wraps the characters at `positions` into
+/// spans with the given attributes
+pub fn highlight_markup(text: &str, positions: &[usize], attributes: &str) -> String {
+    let mut markup = String::with_capacity(text.len());
+    let mut open = false;
+    for (i, c) in text.chars().enumerate() {
+        let matched = !attributes.is_empty() && positions.contains(&i);
+        if matched && !open {
+            markup.push_str(&format!("<span {}>", attributes));
+        } else if !matched && open {
+            markup.push_str("</span>");
+        }
+        open = matched;
+        push_escaped(&mut markup, c);
+    }
+    if open {
+        markup.push_str("</span>");
+    }
+    markup
+}
+
+fn push_escaped(markup: &mut String, c: char) {
+    match c {
+        '&' => markup.push_str("&amp;"),
+        '<' => markup.push_str("&lt;"),
+        '>' => markup.push_str("&gt;"),
+        '"' => markup.push_str("&quot;"),
+        '\'' => markup.push_str("&#39;"),
+        _ => markup.push(c),
+    }
+}
+
+fn fold_case(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+fn boundary_bonus(hay: &[char], j: usize) -> f32 {
+    let current = hay[j];
+    match j.checked_sub(1).map(|p| hay[p]) {
+        None => BONUS_BOUNDARY,
+        Some(prev) if !prev.is_alphanumeric() && current.is_alphanumeric() => BONUS_BOUNDARY,
+        Some(prev) if prev.is_lowercase() && current.is_uppercase() => BONUS_CAMEL_CASE,
+        _ => 0.0,
+    }
+}
+
+#[test]
+fn test_fuzzy_match() {
+    let score = |hay: &str, needle: &str| fuzzy_match(hay, needle).map_or(-1.0, |m| m.score);
+
+    assert_eq!(fuzzy_match("Firefox", "xyz"), None);
+    assert_eq!(fuzzy_match("Firefox", "foxfire"), None);
+    assert!(score("Firefox", "fire") > 0.99);
+    assert_eq!(score("Firefox", ""), 0.0);
+
+    // Acronyms use the word starts
+    let acronym = fuzzy_match("Visual Studio Code", "vsc").unwrap();
+    assert_eq!(acronym.positions, vec![0, 7, 14]);
+
+    // Prefixes beat word starts, which beat scattered matches
+    assert!(score("Code", "co") > score("Visual Studio Code", "co"));
+    assert!(score("Visual Studio Code", "vsc") > score("devscript", "vsc"));
+    assert!(score("Terminal", "term") > score("Xfce Terminal", "term"));
+    assert!(score("Xfce Terminal", "term") > score("Thunderbird Mail", "term"));
+    assert!(score("GNOME Settings", "set") > score("Reset Tool", "set"));
+
+    // Consecutive runs beat scattered characters
+    let run = fuzzy_match("spotify", "tif").unwrap();
+    assert_eq!(run.positions, vec![3, 4, 5]);
+    assert!(score("spotify", "tif") > score("stuff in files", "tif"));
+}
+
+#[test]
+fn test_search() {
+    let app = |query: &str| {
+        search(
+            &[
+                ("Firefox;browser;internet", 1.0),
+                ("Web Browser", 0.6),
+                ("Browse the World Wide Web", 0.4),
+            ],
+            query,
+        )
+    };
+    // Every token has to match
+    assert!(app("fire web").is_some());
+    assert!(app("fire mail").is_none());
+    assert_eq!(app("  "), Some(0.0));
+
+    // Typos in longer tokens are tolerated, but rank lower
+    assert!(app("fierfox").is_some());
+    assert!(app("frefx").is_some());
+    assert!(app("fierfox").unwrap() < app("firefox").unwrap());
+    assert!(app("xyz").is_none());
+
+    // Secondary fields count less
+    let generic = search(&[("Thunderbird", 1.0), ("Mail Client", 0.6)], "mail").unwrap();
+    let name = search(&[("Mailspring", 1.0), ("Mail Client", 0.6)], "mail").unwrap();
+    assert!(name > generic);
+
+    assert_eq!(
+        prefix_distance(&['f', 'i', 'e', 'r'], &['f', 'i', 'r', 'e', 'f']),
+        1
+    );
+    assert_eq!(
+        match_positions("Visual Studio Code", "code vis"),
+        vec![0, 1, 2, 14, 15, 16, 17]
+    );
+}
+
+#[test]
+fn test_highlight_markup() {
+    let bold = "weight=\"bold\"";
+    assert_eq!(highlight_markup("Firefox", &[], bold), "Firefox");
+    assert_eq!(
+        highlight_markup("Firefox", &[0, 1, 4], bold),
+        "<span weight=\"bold\">Fi</span>re<span weight=\"bold\">f</span>ox"
+    );
+    assert_eq!(
+        highlight_markup("AT&T <Tools>", &[2, 3], bold),
+        "AT<span weight=\"bold\">&amp;T</span> &lt;Tools&gt;"
+    );
+    assert_eq!(highlight_markup("Tom & Jerry", &[0], ""), "Tom &amp; Jerry");
+}
@@ -4,6 +4,7 @@ pub mod calc_tile;
 pub mod clipboard_tile;
 pub mod error_tile;
 pub mod event_tile;
+pub mod fuzzy;
 pub mod mpris_tile;
 pub mod pipe_tile;
 pub mod process_tile;
@@ -10,6 +10,7 @@ use gtk4::prelude::BoxExt;
 use gtk4::prelude::WidgetExt;
 use gtk4::Image;
 
+use super::util::match_markup;
 use super::util::SherlockSearch;
 use super::util::TileBuilder;
 use super::Tile;
@@ -19,15 +20,20 @@ impl Tile {
         let mut results: Vec<SherlockRow> = Default::default();
 
         for item in lines {
-            if item.fuzzy_match(keyword) || item.binary.is_some() {
+            if item.search_score(keyword).is_some() || item.binary.is_some() {
                 let builder = TileBuilder::new("/dev/skxxtz/sherlock/ui/tile.ui");
                 builder.object.set_spawn_focus(true);
 
                 if let Some(title) = &item.title {
-                    builder.title.set_text(&title);
+                    builder.title.set_markup(&match_markup(title, keyword));
                 }
                 if let Some(desc) = &item.description {
-                    builder.category.set_text(&desc);
+                    // The description is searched if there is no title
+                    if item.title.is_none() {
+                        builder.category.set_markup(&match_markup(desc, keyword));
+                    } else {
+                        builder.category.set_text(&desc);
+                    }
                 } else {
                     builder.category.set_visible(false);
                 }
@@ -5,7 +5,7 @@ use crate::g_subclasses::sherlock_row::SherlockRow;
 use crate::launcher::process_launcher::ProcessLauncher;
 use crate::launcher::{Launcher, ResultItem};
 
-use super::util::{SherlockSearch, TileBuilder};
+use super::util::{match_markup, SherlockSearch, TileBuilder};
 use super::Tile;
 
 impl Tile {
@@ -17,7 +17,7 @@ impl Tile {
         let mut results: Vec<ResultItem> = Default::default();
 
         for (key, value) in proc.processes.iter() {
-            if value.to_lowercase().fuzzy_match(&keyword.to_lowercase()) {
+            if let Some(relevance) = value.search_score(keyword) {
                 let builder = TileBuilder::new("/dev/skxxtz/sherlock/ui/tile.ui");
                 builder.object.set_spawn_focus(launcher.spawn_focus);
                 builder.object.set_shortcut(launcher.shortcut);
@@ -27,7 +27,7 @@ impl Tile {
                 } else {
                     builder.category.set_visible(false);
                 }
-                builder.title.set_markup(&value);
+                builder.title.set_markup(&match_markup(value, keyword));
                 builder.icon.set_icon_name(Some(&proc.icon));
                 let ppid = key.0;
                 let cpid = key.1;
@@ -57,6 +57,8 @@ impl Tile {
                 };
                 results.push(ResultItem {
                     priority: launcher.priority as f32,
+                    relevance,
+                    exec: None,
                     row_item: builder.object,
                     shortcut_holder,
                 });
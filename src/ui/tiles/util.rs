@@ -1,11 +1,16 @@
 use crate::{
     g_subclasses::sherlock_row::SherlockRow,
     launcher::{Launcher, ResultItem},
-    loader::pipe_loader::PipeData,
+    loader::{
+        pipe_loader::PipeData,
+        util::{default_match_highlight, AppAction, AppData},
+    },
     CONFIG,
 };
 use gtk4::{prelude::*, Box, Builder, Image, Label, Overlay, Spinner, TextView};
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
+
+use super::fuzzy::{fuzzy_match, highlight_markup, match_positions, search};
 
 #[derive(Debug)]
 pub struct AsyncLauncherTile {
@@ -227,23 +232,41 @@ impl WeatherTileBuilder {
     }
 }
 
+// Weights of the secondary app fields relative to name and keywords
+const WEIGHT_GENERIC_NAME: f32 = 0.6;
+const WEIGHT_COMMENT: f32 = 0.4;
+
 pub trait SherlockSearch {
-    fn fuzzy_match<T: AsRef<str>>(&self, substring: T) -> bool;
+    /// Relevance of the item for the query between 0 and 1, or None if it
+    /// doesn't match
+    fn search_score<T: AsRef<str>>(&self, query: T) -> Option<f32>;
 }
 
 impl SherlockSearch for String {
-    fn fuzzy_match<T>(&self, substring: T) -> bool
+    fn search_score<T>(&self, query: T) -> Option<f32>
     where
-        Self: AsRef<str>,
         T: AsRef<str>,
     {
-        let char_pattern: HashSet<char> = substring.as_ref().chars().collect();
-        let concat_str: String = self.chars().filter(|s| char_pattern.contains(s)).collect();
-        concat_str.contains(substring.as_ref())
+        search(&[(self.as_str(), 1.0)], query.as_ref())
+    }
+}
+impl SherlockSearch for AppData {
+    fn search_score<T>(&self, query: T) -> Option<f32>
+    where
+        T: AsRef<str>,
+    {
+        let mut fields = vec![(self.search_string.as_str(), 1.0)];
+        if let Some(generic_name) = &self.generic_name {
+            fields.push((generic_name.as_str(), WEIGHT_GENERIC_NAME));
+        }
+        if let Some(comment) = &self.comment {
+            fields.push((comment.as_str(), WEIGHT_COMMENT));
+        }
+        search(&fields, query.as_ref())
     }
 }
 impl SherlockSearch for PipeData {
-    fn fuzzy_match<T>(&self, substring: T) -> bool
+    fn search_score<T>(&self, query: T) -> Option<f32>
     where
         T: AsRef<str>,
     {
@@ -252,14 +275,36 @@ impl SherlockSearch for PipeData {
             Some(_) => &self.title,
             None => &self.description,
         };
-        if let Some(search_in) = search_in {
-            let char_pattern: HashSet<char> = substring.as_ref().chars().collect();
-            let concat_str: String = search_in
-                .chars()
-                .filter(|s| char_pattern.contains(s))
-                .collect();
-            return concat_str.contains(substring.as_ref());
-        }
-        return false;
+        search_in
+            .as_ref()
+            .and_then(|search_in| search(&[(search_in.as_str(), 1.0)], query.as_ref()))
     }
 }
+
+/// Relevance of an app action for the query. Actions are only listed if part
+/// of the query matches the action's name, so "firefox priv" lists "New
+/// Private Window" but "firefox" doesn't.
+pub fn action_search_score(app: &AppData, action: &AppAction, query: &str) -> Option<f32> {
+    let names_action = query
+        .split_whitespace()
+        .any(|token| fuzzy_match(&action.name, token).is_some());
+    if !names_action {
+        return None;
+    }
+    search(
+        &[
+            (action.name.as_str(), 1.0),
+            (app.search_string.as_str(), 1.0),
+        ],
+        query,
+    )
+}
+
+/// Escaped markup of `text` with the characters matching `keyword` highlighted
+pub fn match_markup(text: &str, keyword: &str) -> String {
+    let attributes = CONFIG.get().map_or_else(default_match_highlight, |c| {
+        c.appearance.match_highlight.clone()
+    });
+    let positions = match_positions(text, keyword);
+    highlight_markup(text, &positions, &attributes)
+}
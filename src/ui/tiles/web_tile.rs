@@ -55,7 +55,9 @@ impl Tile {
             _ => None,
         };
         let res = ResultItem {
-            priority: launcher.priority as f32,
+            priority: web.priority,
+            relevance: 0.0,
+            exec: Some(format!("websearch-{}", web.engine)),
             row_item: builder.object,
             shortcut_holder,
         };
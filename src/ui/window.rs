@@ -6,9 +6,12 @@ use gtk4::{prelude::*, Application, ApplicationWindow};
 use gtk4::{Builder, Stack};
 use gtk4_layer_shell::{Layer, LayerShell};
 
+use crate::actions::failures::{has_failures, take_failures};
 use crate::application::util::reload_content;
+use crate::daemon::dbus;
 use crate::CONFIG;
 
+use super::error_view::errors;
 use super::tiles::util::TextViewTileBuilder;
 
 pub fn window(application: &Application) -> (ApplicationWindow, Stack, Rc<RefCell<String>>) {
@@ -43,6 +46,7 @@ pub fn window(application: &Application) -> (ApplicationWindow, Stack, Rc<RefCel
                 match c.behavior.daemonize {
                     true => {
                         window.hide();
+                        dbus::emit_visible_changed(false);
                         let _ = gtk4::prelude::WidgetExt::activate_action(
                             window,
                             "win.clear-search",
@@ -63,7 +67,19 @@ pub fn window(application: &Application) -> (ApplicationWindow, Stack, Rc<RefCel
                 match c.behavior.daemonize {
                     true => {
                         reload_content(window, &stack_clone, &page_clone);
+                        // Launches that failed while the window was hidden
+                        if has_failures() {
+                            let _ = gtk4::prelude::WidgetExt::activate_action(
+                                window,
+                                "win.show-launch-errors",
+                                None,
+                            );
+                        }
+                        let was_visible = window.is_visible();
                         window.present();
+                        if !was_visible {
+                            dbus::emit_visible_changed(true);
+                        }
                     }
                     false => window.present(),
                 }
@@ -104,11 +120,34 @@ pub fn window(application: &Application) -> (ApplicationWindow, Stack, Rc<RefCel
         })
         .build();
 
+    // Setup action to show the launches that failed since they were last shown
+    let stack_clone = stack.clone();
+    let page_clone = Rc::clone(&current_stack_page);
+    let action_launch_errors = ActionEntry::builder("show-launch-errors")
+        .activate(move |window: &ApplicationWindow, _, _| {
+            let failures = take_failures();
+            if failures.is_empty() {
+                return;
+            }
+            if let Some(page) = stack_clone.child_by_name("error-page") {
+                stack_clone.remove(&page);
+            }
+            let error_stack = errors(&failures, &Vec::new(), &page_clone);
+            stack_clone.add_named(&error_stack, Some("error-page"));
+            let _ = gtk4::prelude::WidgetExt::activate_action(
+                window,
+                "win.switch-page",
+                Some(&String::from("error-page").to_variant()),
+            );
+        })
+        .build();
+
     window.add_action_entries([
         action_close,
         action_open,
         action_stack_switch,
         action_next_page,
+        action_launch_errors,
     ]);
 
     window.set_child(Some(&stack));